@@ -0,0 +1,121 @@
+//! Builds static colliders from the marked nodes of `assets/level/level.gltf` and drops a ball
+//! onto the cooked ground mesh. Runs headless:
+//!
+//! `cargo run --example gltf_colliders --no-default-features --features "empty"`
+
+use amethyst::{
+    assets::{AssetLoaderSystemData, AssetStorage, Handle},
+    core::transform::{Transform, TransformBundle},
+    ecs::{Entities, Entity, Join, Read, ReadStorage, WorldExt},
+    prelude::*,
+    utils::application_root_dir,
+};
+
+use amethyst_physx::{
+    Collider, ColliderShape, GltfColliderFormat, GltfColliders, PhysicsBodies, PhysicsBundle,
+    PhysicsConfig, PhysicsTime, RigidBody,
+};
+
+/// `Ground-col` and `Rock` are marked as colliders, `Decoration` isn't.
+const EXPECTED_COLLIDERS: usize = 2;
+
+/// Frames to wait for the glTF file before giving up.
+const LOAD_TIMEOUT: u32 = 600;
+/// Physics steps to let the ball fall and settle before checking it.
+const SIMULATED_STEPS: u32 = 300;
+
+#[derive(Default)]
+struct GltfCollidersState {
+    frames: u32,
+    steps: u32,
+    level: Option<Handle<GltfColliders>>,
+    ball: Option<Entity>,
+}
+
+impl SimpleState for GltfCollidersState {
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let handle = data.world.exec(|loader: AssetLoaderSystemData<'_, GltfColliders>| {
+            loader.load("level/level.gltf", GltfColliderFormat, ())
+        });
+        data.world.create_entity().with(handle.clone()).with(Transform::default()).build();
+        self.level = Some(handle);
+    }
+
+    fn update(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
+        self.frames += 1;
+
+        let ball = match self.ball {
+            Some(ball) => ball,
+            None => {
+                let level = self.level.as_ref().unwrap();
+                let loaded = data.world.exec(|storage: Read<'_, AssetStorage<GltfColliders>>| {
+                    storage.get(level).map(|asset| asset.colliders.clone())
+                });
+                match loaded {
+                    Some(colliders) => {
+                        let names: Vec<_> = colliders.iter().map(|collider| collider.name.as_str()).collect();
+                        println!("Loaded colliders {:?}", names);
+                        assert_eq!(colliders.len(), EXPECTED_COLLIDERS);
+                    }
+                    None if self.frames > LOAD_TIMEOUT => panic!("level.gltf did not load"),
+                    None => return Trans::None,
+                }
+
+                let mut transform = Transform::default();
+                transform.set_translation_xyz(0.0, 3.0, 0.0);
+                let ball = data
+                    .world
+                    .create_entity()
+                    .with(RigidBody::default())
+                    .with(Collider::new(ColliderShape::Sphere { radius: 0.5 }))
+                    .with(transform)
+                    .build();
+                self.ball = Some(ball);
+                return Trans::None;
+            }
+        };
+
+        self.steps += data.world.read_resource::<PhysicsTime>().steps_last_frame();
+        if self.steps < SIMULATED_STEPS {
+            return Trans::None;
+        }
+
+        data.world.exec(
+            |(entities, bodies, rigid_bodies, colliders, transforms): (
+                Entities<'_>,
+                Read<'_, PhysicsBodies>,
+                ReadStorage<'_, RigidBody>,
+                ReadStorage<'_, Collider>,
+                ReadStorage<'_, Transform>,
+            )| {
+                let static_colliders = (&entities, &colliders, !&rigid_bodies)
+                    .join()
+                    .filter(|(entity, _, ())| bodies.contains(*entity))
+                    .count();
+                let height = transforms.get(ball).unwrap().translation().y;
+                println!("PhysX simulates {} static colliders, the ball rests at {}", static_colliders, height);
+
+                assert_eq!(static_colliders, EXPECTED_COLLIDERS);
+                // Landed on the ground mesh instead of falling through it
+                assert!((height - 0.5).abs() < 0.1);
+            },
+        );
+
+        Trans::Quit
+    }
+}
+
+fn main() -> amethyst::Result<()> {
+    amethyst::start_logger(Default::default());
+
+    let app_root = application_root_dir()?;
+    let assets_dir = app_root.join("assets/");
+
+    let game_data = GameDataBuilder::default()
+        .with_bundle(PhysicsBundle::new(PhysicsConfig::default()).without_debug_render())?
+        .with_bundle(TransformBundle::new().with_dep(&[PhysicsBundle::INTERPOLATION_SYSTEM]))?;
+
+    let mut game = Application::new(assets_dir, GltfCollidersState::default(), game_data)?;
+    game.run();
+    Ok(())
+}
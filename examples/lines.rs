@@ -0,0 +1,360 @@
+//! Displays several lines with both methods.
+
+use amethyst::{
+    assets::{AssetLoaderSystemData, HotReloadBundle, RonFormat},
+    controls::{FlyControlBundle, FlyControlTag},
+    core::{
+        math::{Point3, UnitQuaternion, Vector3},
+        transform::{Transform, TransformBundle},
+        Time,
+    },
+    derive::SystemDesc,
+    ecs::{
+        Component, DenseVecStorage, Join, Read, ReadStorage, System, SystemData, WorldExt, Write,
+        WriteStorage,
+    },
+    input::{is_close_requested, is_key_down, InputBundle, StringBindings},
+    prelude::*,
+    renderer::{
+        camera::{Camera, Projection},
+        debug_drawing::{DebugLines, DebugLinesComponent, DebugLinesParams},
+        palette::Srgba,
+        plugins::{RenderDebugLines, RenderSkybox, RenderToWindow},
+        types::DefaultBackend,
+        RenderingBundle,
+    },
+    utils::application_root_dir,
+    winit::VirtualKeyCode,
+};
+use amethyst_imgui::RenderImgui;
+
+use amethyst_physx::{
+    BodyType, CharacterController, CharacterInputSystem, Collider, ColliderShape, CollisionLayers,
+    GrabSystem, PhysicsBundle, PhysicsConfig, PhysicsMaterial, PickingSystem, PlayerCharacter,
+    RigidBody,
+};
+
+/// Moves the elevator platform up and down.
+struct Elevator {
+    base_height: f32,
+}
+
+impl Component for Elevator {
+    type Storage = DenseVecStorage<Self>;
+}
+
+#[derive(SystemDesc)]
+struct ElevatorSystem;
+
+impl<'s> System<'s> for ElevatorSystem {
+    type SystemData = (
+        ReadStorage<'s, Elevator>,
+        WriteStorage<'s, Transform>,
+        Read<'s, Time>,
+    );
+
+    fn run(&mut self, (elevators, mut transforms, time): Self::SystemData) {
+        let offset = (time.absolute_time_seconds() as f32 * 0.5).sin() * 3.0 + 3.0;
+        for (elevator, transform) in (&elevators, &mut transforms).join() {
+            transform.set_translation_y(elevator.base_height + offset);
+        }
+    }
+}
+
+#[derive(SystemDesc)]
+struct ExampleLinesSystem;
+
+impl<'s> System<'s> for ExampleLinesSystem {
+    type SystemData = (
+        Write<'s, DebugLines>, // Request DebugLines resource
+        Read<'s, Time>,
+    );
+
+    fn run(&mut self, (mut _debug_lines_resource, _time): Self::SystemData) {
+        // Drawing debug lines, as a resource
+        // let t = (time.absolute_time_seconds() as f32).cos();
+
+        // debug_lines_resource.draw_direction(
+        //     [t, 0.0, 0.5].into(),
+        //     [0.0, 0.3, 0.0].into(),
+        //     Srgba::new(0.5, 0.05, 0.65, 1.0),
+        // );
+
+        // debug_lines_resource.draw_line(
+        //     [t, 0.0, 0.5].into(),
+        //     [0.0, 0.0, 0.2].into(),
+        //     Srgba::new(0.5, 0.05, 0.65, 1.0),
+        // );
+        amethyst_imgui::with(|ui| {
+            ui.show_demo_window(&mut true);
+        });
+    }
+}
+
+const SPHERE_SIZE: f32 = 2.0;
+
+// #[derive(SystemDesc)]
+// struct RenderPhysXSystem;
+// impl<'a> System<'a> for RenderPhysXSystem {
+//     type SystemData = (Write<'a, Box<Scene>>);
+
+//     fn run(&mut self, (scene): Self::SystemData) {
+//         let buffer = scene.get_render_buffer();
+//     }
+// }
+
+struct ExampleState{
+}
+impl ExampleState{
+    fn new() -> ExampleState {
+        ExampleState{}
+    }
+}
+impl SimpleState for ExampleState {
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        // Setup debug lines as a resource
+        data.world.insert(DebugLines::new());
+        // Configure width of lines. Optional step
+        data.world.insert(DebugLinesParams { line_width: 2.0 });
+
+        // Edit the materials while the example runs to see them hot reload
+        let (ground_material, rubber_material) = data.world.exec(|loader: AssetLoaderSystemData<'_, PhysicsMaterial>| {
+            (
+                loader.load("materials/ground.ron", RonFormat, ()),
+                loader.load("materials/rubber.ron", RonFormat, ()),
+            )
+        });
+
+        // Planes face +X, rotate the ground so it faces up
+        let mut ground_transform = Transform::default();
+        ground_transform.set_rotation(UnitQuaternion::from_axis_angle(&Vector3::z_axis(), std::f32::consts::FRAC_PI_2));
+        data.world
+            .create_entity()
+            .with(Collider::new(ColliderShape::Plane).with_material(ground_material))
+            .with(ground_transform)
+            .build();
+
+        let mut sphere_transform = Transform::default();
+        sphere_transform.set_translation_xyz(1.0, 40.0, -4.0);
+        data.world
+            .create_entity()
+            .with(RigidBody { density: 10.0, angular_damping: 0.5, ..Default::default() })
+            .with(
+                Collider::new(ColliderShape::Sphere { radius: SPHERE_SIZE })
+                    .with_material(rubber_material)
+                    .with_layer("debris"),
+            )
+            .with(sphere_transform)
+            .build();
+
+        // Walk around with the arrow keys and jump with space
+        let mut character_transform = Transform::default();
+        character_transform.set_translation_xyz(0.0, 2.0, 0.0);
+        data.world
+            .create_entity()
+            .with(CharacterController::default())
+            .with(PlayerCharacter)
+            .with(character_transform)
+            .build();
+
+        // A crate to push around and an elevator to ride
+        let mut crate_transform = Transform::default();
+        crate_transform.set_translation_xyz(3.0, 0.5, 0.0);
+        data.world
+            .create_entity()
+            .with(RigidBody::default())
+            .with(Collider::new(ColliderShape::Box { half_extents: [0.5, 0.5, 0.5] }).with_layer("props"))
+            .with(crate_transform)
+            .build();
+
+        let mut elevator_transform = Transform::default();
+        elevator_transform.set_translation_xyz(-4.0, 0.25, 0.0);
+        data.world
+            .create_entity()
+            .with(RigidBody::new(BodyType::Kinematic))
+            .with(Collider::new(ColliderShape::Box { half_extents: [1.5, 0.25, 1.5] }))
+            .with(Elevator { base_height: 0.25 })
+            .with(elevator_transform)
+            .build();
+
+        // Setup debug lines as a component and add lines to render axis&grid
+        let mut debug_lines_component = DebugLinesComponent::with_capacity(100);
+        // debug_lines_component.add_direction(
+        //     [0.0, 0.0001, 0.0].into(),
+        //     [0.2, 0.0, 0.0].into(),
+        //     Srgba::new(1.0, 0.0, 0.23, 1.0),
+        // );
+        // debug_lines_component.add_direction(
+        //     [0.0, 0.0, 0.0].into(),
+        //     [0.0, 0.2, 0.0].into(),
+        //     Srgba::new(0.5, 0.85, 0.1, 1.0),
+        // );
+        // debug_lines_component.add_direction(
+        //     [0.0, 0.0001, 0.0].into(),
+        //     [0.0, 0.0, 0.2].into(),
+        //     Srgba::new(0.2, 0.75, 0.93, 1.0),
+        // );
+
+        let width: u32 = 10;
+        let depth: u32 = 10;
+        let main_color = Srgba::new(0.4, 0.4, 0.4, 1.0);
+
+        // Grid lines in X-axis
+        for x in 0..=width {
+            let (x, width, depth) = (x as f32, width as f32, depth as f32);
+
+            let position = Point3::new(x - width / 2.0, 0.0, -depth / 2.0);
+            let direction = Vector3::new(0.0, 0.0, depth);
+
+            debug_lines_component.add_direction(position, direction, main_color);
+
+            // Sub-grid lines
+            if (x - width).abs() < 0.0001 {
+                for sub_x in 1..10 {
+                    let sub_offset = Vector3::new((1.0 / 10.0) * sub_x as f32, -0.001, 0.0);
+
+                    debug_lines_component.add_direction(
+                        position + sub_offset,
+                        direction,
+                        Srgba::new(0.1, 0.1, 0.1, 0.2),
+                    );
+                }
+            }
+        }
+
+        // Grid lines in Z-axis
+        for z in 0..=depth {
+            let (z, width, depth) = (z as f32, width as f32, depth as f32);
+
+            let position = Point3::new(-width / 2.0, 0.0, z - depth / 2.0);
+            let direction = Vector3::new(width, 0.0, 0.0);
+
+            debug_lines_component.add_direction(position, direction, main_color);
+
+            // Sub-grid lines
+            if (z - depth).abs() < 0.0001 {
+                for sub_z in 1..10 {
+                    let sub_offset = Vector3::new(0.0, -0.001, (1.0 / 10.0) * sub_z as f32);
+
+                    debug_lines_component.add_direction(
+                        position + sub_offset,
+                        direction,
+                        Srgba::new(0.1, 0.1, 0.1, 0.2),
+                    );
+                }
+            }
+        }
+        data.world.register::<DebugLinesComponent>();
+        data.world
+            .create_entity()
+            .with(debug_lines_component)
+            .build();
+
+        // Setup camera
+        let mut local_transform = Transform::default();
+        local_transform.set_translation_xyz(0.0, 0.5, 2.0);
+        data.world
+            .create_entity()
+            .with(FlyControlTag)
+            .with(Camera::from(Projection::perspective(
+                1.33333,
+                std::f32::consts::FRAC_PI_2,
+                0.1,
+                1000.0,
+            )))
+            .with(local_transform)
+            .build();
+    
+
+    
+        // unsafe {
+        //     scene.release();
+        // }
+    
+        // drop(physics);
+    
+        // foundation.release();
+    }
+
+    fn handle_event(
+        &mut self,
+        _: StateData<'_, GameData<'_, '_>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        if let StateEvent::Window(event) = event {
+            if is_close_requested(&event) || is_key_down(&event, VirtualKeyCode::Escape) {
+                Trans::Quit
+            } else {
+                Trans::None
+            }
+        } else {
+            Trans::None
+        }
+    }
+
+    fn fixed_update(&mut self, _data: StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
+        Trans::None
+    }
+
+    // fn update(&mut self, _data: &mut StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
+        
+
+    //     Trans::None
+    // }
+}
+
+fn main() -> amethyst::Result<()> {
+    amethyst::start_logger(Default::default());
+
+    let app_root = application_root_dir()?;
+
+    let display_config_path = app_root.join("config/display.ron");
+    let key_bindings_path = app_root.join("config/input.ron");
+    let physics_config = PhysicsConfig::load(app_root.join("config/physics.ron"))?;
+    let collision_layers = CollisionLayers::load(app_root.join("config/collision_layers.ron"))?;
+    let assets_dir = app_root.join("assets/");
+
+    let fly_control_bundle = FlyControlBundle::<StringBindings>::new(
+        Some(String::from("move_x")),
+        Some(String::from("move_y")),
+        Some(String::from("move_z")),
+    )
+    .with_sensitivity(0.1, 0.1);
+
+    let game_data = GameDataBuilder::default()
+        .with_bundle(
+            InputBundle::<StringBindings>::new().with_bindings_from_file(&key_bindings_path)?,
+        )?
+        .with_bundle(HotReloadBundle::default())?
+        .with(ExampleLinesSystem, "example_lines_system", &[])
+        .with(
+            CharacterInputSystem::<StringBindings>::new(
+                String::from("character_x"),
+                String::from("character_z"),
+                String::from("jump"),
+            ),
+            "character_input",
+            &["input_system"],
+        )
+        .with(ElevatorSystem, "elevator", &[])
+        .with_bundle(PhysicsBundle::new(physics_config).with_collision_layers(collision_layers))?
+        .with_bundle(fly_control_bundle)?
+        .with_bundle(TransformBundle::new().with_dep(&["fly_movement", PhysicsBundle::INTERPOLATION_SYSTEM]))?
+        .with(PickingSystem::<StringBindings>::default(), "physics_picking", &["transform_system"])
+        .with(
+            GrabSystem::<StringBindings>::new(String::from("grab"), 200.0, 20.0),
+            "physics_grab",
+            &["physics_picking"],
+        )
+        .with_bundle(
+            RenderingBundle::<DefaultBackend>::new()
+                .with_plugin(RenderToWindow::from_config_path(display_config_path)?)
+                .with_plugin(RenderDebugLines::default())
+                .with_plugin(RenderSkybox::default())
+                .with_plugin(RenderImgui::<amethyst::input::StringBindings>::default())
+        )?;
+
+    let mut game = Application::new(assets_dir, ExampleState::new(), game_data)?;
+    game.run();
+    Ok(())
+}
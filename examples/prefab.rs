@@ -0,0 +1,114 @@
+//! Spawns the ground, a stack of boxes and a ball from `assets/prefab/level.ron` and checks that
+//! PhysX simulates all of them. Runs headless:
+//!
+//! `cargo run --example prefab --no-default-features --features "empty"`
+
+use amethyst::{
+    assets::{PrefabLoader, PrefabLoaderSystemDesc, RonFormat},
+    core::transform::{Transform, TransformBundle},
+    derive::PrefabData,
+    ecs::{Entities, Join, Read, ReadStorage, WorldExt},
+    prelude::*,
+    utils::application_root_dir,
+};
+use serde::{Deserialize, Serialize};
+
+use amethyst_physx::{Collider, PhysicsBodies, PhysicsBundle, PhysicsConfig, PhysicsPrefab, RigidBody};
+
+/// The level has a static ground, three boxes and a ball.
+const EXPECTED_STATIC: usize = 1;
+const EXPECTED_DYNAMIC: usize = 4;
+
+/// Frames to wait for the prefab and its materials before giving up.
+const LOAD_TIMEOUT: u32 = 600;
+/// Frames to simulate the level before checking it.
+const SIMULATED_FRAMES: u32 = 60;
+
+#[derive(Debug, Default, Deserialize, Serialize, PrefabData)]
+#[serde(default)]
+struct LevelPrefab {
+    transform: Option<Transform>,
+    physics: Option<PhysicsPrefab>,
+}
+
+#[derive(Default)]
+struct PrefabState {
+    frames: u32,
+    loaded: bool,
+}
+
+impl SimpleState for PrefabState {
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let handle = data.world.exec(|loader: PrefabLoader<'_, LevelPrefab>| {
+            loader.load("prefab/level.ron", RonFormat, ())
+        });
+        data.world.create_entity().with(handle).build();
+    }
+
+    fn update(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
+        self.frames += 1;
+
+        let (simulated_static, simulated_dynamic, lowest_body) = data.world.exec(
+            |(entities, bodies, rigid_bodies, colliders, transforms): (
+                Entities<'_>,
+                Read<'_, PhysicsBodies>,
+                ReadStorage<'_, RigidBody>,
+                ReadStorage<'_, Collider>,
+                ReadStorage<'_, Transform>,
+            )| {
+                let simulated = |entity| bodies.contains(entity);
+                let simulated_static = (&entities, &colliders, !&rigid_bodies)
+                    .join()
+                    .filter(|(entity, _, ())| simulated(*entity))
+                    .count();
+                let simulated_dynamic = (&entities, &rigid_bodies).join().filter(|(entity, _)| simulated(*entity)).count();
+                let lowest_body = (&rigid_bodies, &transforms)
+                    .join()
+                    .map(|(_, transform)| transform.translation().y)
+                    .fold(std::f32::MAX, f32::min);
+                (simulated_static, simulated_dynamic, lowest_body)
+            },
+        );
+
+        if !self.loaded {
+            if simulated_static + simulated_dynamic == EXPECTED_STATIC + EXPECTED_DYNAMIC {
+                self.loaded = true;
+                self.frames = 0;
+            } else if self.frames > LOAD_TIMEOUT {
+                panic!("level.ron did not spawn all bodies");
+            }
+            return Trans::None;
+        }
+
+        if self.frames < SIMULATED_FRAMES {
+            return Trans::None;
+        }
+
+        println!(
+            "PhysX simulates {} static and {} dynamic bodies, the lowest body rests at {}",
+            simulated_static, simulated_dynamic, lowest_body
+        );
+        assert_eq!(simulated_static, EXPECTED_STATIC);
+        assert_eq!(simulated_dynamic, EXPECTED_DYNAMIC);
+        // Resting on the ground instead of falling through it
+        assert!(lowest_body > 0.0);
+
+        Trans::Quit
+    }
+}
+
+fn main() -> amethyst::Result<()> {
+    amethyst::start_logger(Default::default());
+
+    let app_root = application_root_dir()?;
+    let assets_dir = app_root.join("assets/");
+
+    let game_data = GameDataBuilder::default()
+        .with_system_desc(PrefabLoaderSystemDesc::<LevelPrefab>::default(), "level_prefab", &[])
+        .with_bundle(PhysicsBundle::new(PhysicsConfig::default()).without_debug_render())?
+        .with_bundle(TransformBundle::new().with_dep(&[PhysicsBundle::INTERPOLATION_SYSTEM]))?;
+
+    let mut game = Application::new(assets_dir, PrefabState::default(), game_data)?;
+    game.run();
+    Ok(())
+}
@@ -0,0 +1,138 @@
+//! Generates a ragdoll from the skin in `assets/ragdoll/skeleton.gltf` and checks the number of
+//! bodies and joints PhysX ends up simulating. Runs headless:
+//!
+//! `cargo run --example ragdoll --no-default-features --features "empty"`
+
+use amethyst::{
+    animation::Skin,
+    assets::{AssetLoaderSystemData, PrefabLoaderSystemDesc},
+    core::{transform::{Transform, TransformBundle}, ParentHierarchy},
+    ecs::{Entities, Entity, Join, Read, ReadExpect, ReadStorage, WorldExt},
+    gltf::{GltfPrefab, GltfSceneAsset, GltfSceneFormat, GltfSceneLoaderSystemDesc},
+    prelude::*,
+    utils::application_root_dir,
+};
+
+use amethyst_physx::{
+    PhysicsArticulations, PhysicsBodies, PhysicsBundle, PhysicsConfig, Ragdoll, RagdollConfig,
+    RagdollMode,
+};
+
+/// The sample skeleton has hips with a spine, head and leg below them.
+const EXPECTED_BODIES: usize = 4;
+const EXPECTED_JOINTS: usize = 3;
+
+/// Frames to wait for the glTF scene before giving up.
+const LOAD_TIMEOUT: u32 = 600;
+/// Frames to simulate the ragdoll before checking it.
+const SIMULATED_FRAMES: u32 = 30;
+
+#[derive(Default)]
+struct RagdollState {
+    frames: u32,
+    skin: Option<Entity>,
+}
+
+impl SimpleState for RagdollState {
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let handle = data.world.exec(|loader: AssetLoaderSystemData<'_, GltfSceneAsset>| {
+            loader.load("ragdoll/skeleton.gltf", GltfSceneFormat::default(), ())
+        });
+        data.world.create_entity().with(handle).build();
+    }
+
+    fn update(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
+        self.frames += 1;
+
+        let skin = match self.skin {
+            Some(skin) => skin,
+            None => {
+                let generated = data.world.exec(
+                    |(entities, skins, transforms, hierarchy): (
+                        Entities<'_>,
+                        ReadStorage<'_, Skin>,
+                        ReadStorage<'_, Transform>,
+                        ReadExpect<'_, ParentHierarchy>,
+                    )| {
+                        (&entities, &skins).join().next().map(|(entity, skin)| {
+                            (entity, Ragdoll::from_skin(skin, &transforms, &hierarchy, &RagdollConfig::default()))
+                        })
+                    },
+                );
+
+                match generated {
+                    Some((skin, mut ragdoll)) => {
+                        println!(
+                            "Generated {} bodies and {} joints",
+                            ragdoll.body_count(),
+                            ragdoll.joint_count()
+                        );
+                        assert_eq!(ragdoll.body_count(), EXPECTED_BODIES);
+                        assert_eq!(ragdoll.joint_count(), EXPECTED_JOINTS);
+
+                        ragdoll.mode = RagdollMode::Simulated;
+                        data.world
+                            .write_storage::<Ragdoll>()
+                            .insert(skin, ragdoll)
+                            .expect("Failed to insert the Ragdoll");
+                        self.skin = Some(skin);
+                        self.frames = 0;
+                    }
+                    None if self.frames > LOAD_TIMEOUT => panic!("skeleton.gltf did not load"),
+                    None => {}
+                }
+                return Trans::None;
+            }
+        };
+
+        if self.frames < SIMULATED_FRAMES {
+            return Trans::None;
+        }
+
+        data.world.exec(
+            |(ragdolls, bodies, articulations): (
+                ReadStorage<'_, Ragdoll>,
+                Read<'_, PhysicsBodies>,
+                Read<'_, PhysicsArticulations>,
+            )| {
+                let ragdoll = ragdolls.get(skin).unwrap();
+                let simulated: Vec<_> = ragdoll.bones.iter().filter(|bone| bodies.contains(bone.entity)).collect();
+                let joints = simulated.iter().filter(|bone| bone.link.is_some()).count();
+                let roots = ragdoll
+                    .bones
+                    .iter()
+                    .filter(|bone| articulations.articulation(bone.entity).is_some())
+                    .count();
+                println!(
+                    "PhysX simulates {} bodies and {} joints in {} articulation(s)",
+                    simulated.len(),
+                    joints,
+                    roots
+                );
+
+                assert_eq!(simulated.len(), EXPECTED_BODIES);
+                assert_eq!(joints, EXPECTED_JOINTS);
+                assert_eq!(roots, 1);
+            },
+        );
+
+        Trans::Quit
+    }
+}
+
+fn main() -> amethyst::Result<()> {
+    amethyst::start_logger(Default::default());
+
+    let app_root = application_root_dir()?;
+    let assets_dir = app_root.join("assets/");
+
+    let game_data = GameDataBuilder::default()
+        .with_system_desc(PrefabLoaderSystemDesc::<GltfPrefab>::default(), "gltf_prefab", &[])
+        .with_system_desc(GltfSceneLoaderSystemDesc::default(), "gltf_loader", &["gltf_prefab"])
+        .with_bundle(PhysicsBundle::new(PhysicsConfig::default()).without_debug_render())?
+        .with_bundle(TransformBundle::new().with_dep(&[PhysicsBundle::INTERPOLATION_SYSTEM]))?;
+
+    let mut game = Application::new(assets_dir, RagdollState::default(), game_data)?;
+    game.run();
+    Ok(())
+}
@@ -0,0 +1,532 @@
+use amethyst::{
+    assets::AssetStorage,
+    core::{math::Isometry3, transform::Transform, ParentHierarchy},
+    derive::SystemDesc,
+    ecs::{
+        hibitset::BitSetLike, storage::ComponentEvent, world::Index, Component, DenseVecStorage,
+        Entities, Entity, FlaggedStorage, Join, Read, ReadExpect, ReadStorage, ReaderId, System,
+        SystemData, Write, WriteStorage,
+    },
+};
+use physx::prelude::*;
+use physx_sys::{
+    PxArticulationAxis, PxArticulationBase, PxArticulationBase_createLink_mut,
+    PxArticulationBase_release_mut, PxArticulationDriveType, PxArticulationFlag,
+    PxArticulationJointBase, PxArticulationJointBase_setChildPose_mut,
+    PxArticulationJointBase_setParentPose_mut,
+    PxArticulationJointReducedCoordinate, PxArticulationJointReducedCoordinate_setDriveTarget_mut,
+    PxArticulationJointReducedCoordinate_setDriveVelocity_mut,
+    PxArticulationJointReducedCoordinate_setDrive_mut,
+    PxArticulationJointReducedCoordinate_setJointType_mut,
+    PxArticulationJointReducedCoordinate_setLimit_mut,
+    PxArticulationJointReducedCoordinate_setMotion_mut, PxArticulationJointType,
+    PxArticulationLink, PxArticulationLink_getInboundJoint, PxArticulationMotion,
+    PxArticulationReducedCoordinate, PxArticulationReducedCoordinate_setArticulationFlag_mut,
+    PxPhysics_createArticulationReducedCoordinate_mut, PxRigidActor, PxRigidActor_getGlobalPose,
+    PxRigidBody, PxRigidBodyExt_updateMassAndInertia_mut_1, PxScene_addArticulation_mut,
+    PxScene_removeArticulation_mut,
+};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::ops::DerefMut;
+use std::ptr::{null, null_mut};
+
+use crate::bodies::PhysicsBodies;
+use crate::collider::{supported_collider, Collider};
+use crate::joint::LimitPair;
+use crate::layers::CollisionLayers;
+use crate::material::{materials_loaded, PhysicsMaterial};
+use crate::resources::{PhysXRef, PhysxResources};
+use crate::rigid_body::BodyType;
+use crate::transform_conv::{
+    apply_px_transform, apply_world_pose, global_isometry, isometry_to_px, px_to_isometry,
+};
+
+/// Degree of freedom of an articulation joint, in the joint's parent frame.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ArticulationAxis {
+    Twist,
+    Swing1,
+    Swing2,
+    X,
+    Y,
+    Z,
+}
+
+impl ArticulationAxis {
+    fn to_px(self) -> PxArticulationAxis::Enum {
+        match self {
+            ArticulationAxis::Twist => PxArticulationAxis::eTWIST,
+            ArticulationAxis::Swing1 => PxArticulationAxis::eSWING1,
+            ArticulationAxis::Swing2 => PxArticulationAxis::eSWING2,
+            ArticulationAxis::X => PxArticulationAxis::eX,
+            ArticulationAxis::Y => PxArticulationAxis::eY,
+            ArticulationAxis::Z => PxArticulationAxis::eZ,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArticulationJointKind {
+    Fixed,
+    /// Rotates around the twist axis.
+    Revolute,
+    /// Slides along the X axis.
+    Prismatic,
+    /// Rotates freely around twist and both swing axes.
+    Spherical,
+}
+
+impl ArticulationJointKind {
+    /// The axes this kind of joint can move along.
+    pub fn axes(self) -> &'static [ArticulationAxis] {
+        match self {
+            ArticulationJointKind::Fixed => &[],
+            ArticulationJointKind::Revolute => &[ArticulationAxis::Twist],
+            ArticulationJointKind::Prismatic => &[ArticulationAxis::X],
+            ArticulationJointKind::Spherical => {
+                &[ArticulationAxis::Twist, ArticulationAxis::Swing1, ArticulationAxis::Swing2]
+            }
+        }
+    }
+}
+
+/// Spring drive on one axis of an articulation joint.
+#[derive(Clone, Copy, Debug)]
+pub struct ArticulationDrive {
+    pub stiffness: f32,
+    pub damping: f32,
+    pub max_force: f32,
+    /// Target position on the axis, in radians or meters.
+    pub target: f32,
+    pub target_velocity: f32,
+}
+
+/// Joint connecting an `ArticulationLink` to its parent link.
+#[derive(Clone, Debug)]
+pub struct ArticulationJoint {
+    pub kind: ArticulationJointKind,
+    /// Joint frame relative to the parent link, defaults to the link's local `Transform` so the
+    /// joint sits at the link's origin.
+    pub parent_frame: Option<Isometry3<f32>>,
+    /// Joint frame relative to the link itself.
+    pub child_frame: Isometry3<f32>,
+    /// Axes without a limit move freely.
+    pub limits: Vec<(ArticulationAxis, LimitPair)>,
+    pub drives: Vec<(ArticulationAxis, ArticulationDrive)>,
+}
+
+impl ArticulationJoint {
+    pub fn new(kind: ArticulationJointKind) -> Self {
+        ArticulationJoint {
+            kind,
+            parent_frame: None,
+            child_frame: Isometry3::identity(),
+            limits: Vec::new(),
+            drives: Vec::new(),
+        }
+    }
+
+    pub fn with_limit(mut self, axis: ArticulationAxis, lower: f32, upper: f32) -> Self {
+        self.limits.push((axis, LimitPair { lower, upper }));
+        self
+    }
+
+    pub fn with_drive(mut self, axis: ArticulationAxis, drive: ArticulationDrive) -> Self {
+        self.drives.push((axis, drive));
+        self
+    }
+
+    /// Sets a drive's target position, for posing robotic arms at runtime.
+    pub fn set_drive_target(&mut self, axis: ArticulationAxis, target: f32) {
+        if let Some((_, drive)) = self.drives.iter_mut().find(|(drive_axis, _)| *drive_axis == axis) {
+            drive.target = target;
+        }
+    }
+
+    /// Applies the limits and drives, which unlike the joint type can change while simulated.
+    unsafe fn apply_settings(&self, joint: *mut PxArticulationJointReducedCoordinate) {
+        for &axis in self.kind.axes() {
+            let motion = match self.limits.iter().find(|(limit_axis, _)| *limit_axis == axis) {
+                Some((_, limit)) => {
+                    PxArticulationJointReducedCoordinate_setLimit_mut(joint, axis.to_px(), limit.lower, limit.upper);
+                    PxArticulationMotion::eLIMITED
+                }
+                None => PxArticulationMotion::eFREE,
+            };
+            PxArticulationJointReducedCoordinate_setMotion_mut(joint, axis.to_px(), motion);
+        }
+
+        for (axis, drive) in &self.drives {
+            PxArticulationJointReducedCoordinate_setDrive_mut(
+                joint,
+                axis.to_px(),
+                drive.stiffness,
+                drive.damping,
+                drive.max_force,
+                PxArticulationDriveType::eFORCE,
+            );
+            PxArticulationJointReducedCoordinate_setDriveTarget_mut(joint, axis.to_px(), drive.target);
+            PxArticulationJointReducedCoordinate_setDriveVelocity_mut(joint, axis.to_px(), drive.target_velocity);
+        }
+    }
+}
+
+/// Root of a reduced coordinate articulation, the root entity is its base link.
+///
+/// Every descendant entity with an `ArticulationLink` and a `Transform`, connected through
+/// `Parent` components, becomes a link of the articulation. Links take their shapes from their
+/// `Collider` and their simulated poses are written back to their local `Transform`s. A root
+/// with a `Parent` is placed using the parent's global matrix from the last frame. Adding or
+/// removing links rebuilds the whole articulation. An articulation is built once the
+/// `PhysicsMaterial` assets of its links' colliders have loaded.
+#[derive(Clone, Debug)]
+pub struct Articulation {
+    /// Fixes the base link in place, for robotic arms mounted to the world.
+    pub fix_base: bool,
+    /// Density of the base link.
+    pub density: f32,
+}
+
+impl Default for Articulation {
+    fn default() -> Self {
+        Articulation {
+            fix_base: false,
+            density: 1.0,
+        }
+    }
+}
+
+impl Component for Articulation {
+    type Storage = FlaggedStorage<Self, DenseVecStorage<Self>>;
+}
+
+/// A link of the `Articulation` the entity descends from.
+#[derive(Clone, Debug)]
+pub struct ArticulationLink {
+    pub joint: ArticulationJoint,
+    /// Used to compute mass and inertia from the attached `Collider`.
+    pub density: f32,
+}
+
+impl ArticulationLink {
+    pub fn new(joint: ArticulationJoint) -> Self {
+        ArticulationLink { joint, density: 1.0 }
+    }
+}
+
+impl Component for ArticulationLink {
+    type Storage = FlaggedStorage<Self, DenseVecStorage<Self>>;
+}
+
+struct BuiltLink {
+    entity: Entity,
+    link: usize,
+    /// `None` for the base link.
+    parent: Option<usize>,
+}
+
+struct BuiltArticulation {
+    root: Entity,
+    articulation: usize,
+    /// Parents come before their children.
+    links: Vec<BuiltLink>,
+}
+
+/// Map between `Articulation` roots and the PhysX articulations built for them.
+#[derive(Default)]
+pub struct PhysicsArticulations {
+    by_root: HashMap<Index, BuiltArticulation>,
+    /// Root index of every entity that is part of an articulation, the root included.
+    root_of: HashMap<Index, Index>,
+}
+
+impl PhysicsArticulations {
+    pub fn articulation(&self, root: Entity) -> Option<*mut PxArticulationReducedCoordinate> {
+        self.by_root
+            .get(&root.id())
+            .filter(|built| built.root == root)
+            .map(|built| built.articulation as *mut PxArticulationReducedCoordinate)
+    }
+
+    /// Whether the entity at `index` is the root or a link of an articulation, whose actors are
+    /// managed by `ArticulationSystem` rather than the rigid body systems.
+    pub(crate) fn owns_index(&self, index: Index) -> bool {
+        self.root_of.contains_key(&index)
+    }
+
+    unsafe fn release(&mut self, root: Index, physx: &mut PhysxResources, bodies: &mut PhysicsBodies) {
+        if let Some(built) = self.by_root.remove(&root) {
+            for link in &built.links {
+                self.root_of.remove(&link.entity.id());
+                bodies.remove_index(link.entity.id());
+            }
+            let articulation = built.articulation as *mut PxArticulationBase;
+            PxScene_removeArticulation_mut(physx.scene.get_raw_mut(), articulation, true);
+            PxArticulationBase_release_mut(articulation);
+        }
+    }
+}
+
+/// Builds PhysX articulations for `Articulation` roots and their links, and writes the simulated
+/// link poses back to `Transform`. Runs after the PhysX step and before `RigidBodySyncSystem`,
+/// which leaves the colliders of links alone.
+#[derive(SystemDesc)]
+pub struct ArticulationSystem {
+    #[system_desc(flagged_storage_reader(Articulation))]
+    articulation_reader: ReaderId<ComponentEvent>,
+    #[system_desc(flagged_storage_reader(ArticulationLink))]
+    link_reader: ReaderId<ComponentEvent>,
+}
+
+impl ArticulationSystem {
+    pub fn new(articulation_reader: ReaderId<ComponentEvent>, link_reader: ReaderId<ComponentEvent>) -> Self {
+        ArticulationSystem { articulation_reader, link_reader }
+    }
+}
+
+impl<'a> System<'a> for ArticulationSystem {
+    type SystemData = (
+        Entities<'a>,
+        Write<'a, PhysXRef>,
+        Write<'a, PhysicsBodies>,
+        Write<'a, PhysicsArticulations>,
+        Read<'a, AssetStorage<PhysicsMaterial>>,
+        Read<'a, CollisionLayers>,
+        ReadExpect<'a, ParentHierarchy>,
+        ReadStorage<'a, Articulation>,
+        ReadStorage<'a, ArticulationLink>,
+        ReadStorage<'a, Collider>,
+        WriteStorage<'a, Transform>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            physx,
+            mut bodies,
+            mut articulations,
+            material_storage,
+            layers,
+            hierarchy,
+            roots,
+            links,
+            colliders,
+            mut transforms,
+        ): Self::SystemData,
+    ) {
+        let mut physx_lock = physx.0.as_ref().unwrap().lock().unwrap();
+        let physx_ref = physx_lock.deref_mut();
+
+        // Added and removed links rebuild the articulation, modified ones only update their limits
+        // and drives
+        let mut rebuild = HashSet::new();
+        let mut modified = HashSet::new();
+        for event in roots.channel().read(&mut self.articulation_reader) {
+            match event {
+                ComponentEvent::Modified(index) | ComponentEvent::Removed(index) => {
+                    rebuild.insert(*index);
+                }
+                ComponentEvent::Inserted(_) => {}
+            }
+        }
+        for event in links.channel().read(&mut self.link_reader) {
+            match event {
+                ComponentEvent::Modified(index) => {
+                    modified.insert(*index);
+                }
+                ComponentEvent::Removed(index) => {
+                    rebuild.insert(*index);
+                }
+                ComponentEvent::Inserted(index) => {
+                    // Rebuild the articulation the new link's parent belongs to
+                    let mut current = entities.entity(*index);
+                    while let Some(parent) = hierarchy.parent(current) {
+                        if articulations.owns_index(parent.id()) {
+                            rebuild.insert(parent.id());
+                            break;
+                        }
+                        current = parent;
+                    }
+                }
+            }
+        }
+        for index in rebuild {
+            if let Some(root) = articulations.root_of.get(&index).copied() {
+                unsafe { articulations.release(root, physx_ref, &mut bodies) };
+            }
+        }
+
+        for (root, articulation) in (&entities, &roots).join() {
+            if articulations.by_root.contains_key(&root.id()) || !transforms.contains(root) {
+                continue;
+            }
+            let link_colliders = articulation_entities(root, &hierarchy, &links, &transforms)
+                .into_iter()
+                .filter_map(|entity| colliders.get(entity));
+            if !materials_loaded(link_colliders, &material_storage) {
+                continue;
+            }
+            let built = unsafe {
+                build_articulation(
+                    root,
+                    articulation,
+                    physx_ref,
+                    &material_storage,
+                    &layers,
+                    &hierarchy,
+                    &links,
+                    &colliders,
+                    &transforms,
+                )
+            };
+            for link in &built.links {
+                articulations.root_of.insert(link.entity.id(), root.id());
+                bodies.insert(link.entity, BodyHandle(link.link), BodyType::Dynamic);
+            }
+            articulations.by_root.insert(root.id(), built);
+        }
+
+        for built in articulations.by_root.values() {
+            for link in &built.links {
+                unsafe {
+                    if link.parent.is_some() && modified.contains(&link.entity.id()) {
+                        if let Some(link_component) = links.get(link.entity) {
+                            let joint = PxArticulationLink_getInboundJoint(link.link as *const PxArticulationLink)
+                                as *mut PxArticulationJointReducedCoordinate;
+                            link_component.joint.apply_settings(joint);
+                        }
+                    }
+
+                    let pose = PxRigidActor_getGlobalPose(link.link as *const PxRigidActor);
+                    let parent_global = hierarchy
+                        .parent(link.entity)
+                        .and_then(|parent| transforms.get(parent))
+                        .map(|parent| *parent.global_matrix());
+                    if let Some(transform) = transforms.get_mut(link.entity) {
+                        match (link.parent, parent_global) {
+                            (Some(parent), _) => {
+                                let parent_pose = px_to_isometry(&PxRigidActor_getGlobalPose(parent as *const PxRigidActor));
+                                *transform.isometry_mut() = parent_pose.inverse() * px_to_isometry(&pose);
+                            }
+                            (None, Some(parent_global)) => apply_world_pose(&px_to_isometry(&pose), &parent_global, transform),
+                            (None, None) => apply_px_transform(&pose, transform),
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The root and every link below it that `build_articulation` turns into a PhysX link, parents
+/// before their children.
+fn articulation_entities(
+    root: Entity,
+    hierarchy: &ParentHierarchy,
+    links: &ReadStorage<'_, ArticulationLink>,
+    transforms: &WriteStorage<'_, Transform>,
+) -> Vec<Entity> {
+    let mut entities = vec![root];
+    let mut next = 0;
+    while next < entities.len() {
+        let parent = entities[next];
+        entities.extend(
+            hierarchy
+                .children(parent)
+                .iter()
+                .filter(|child| links.contains(**child) && transforms.contains(**child))
+                .copied(),
+        );
+        next += 1;
+    }
+    entities
+}
+
+unsafe fn build_articulation(
+    root: Entity,
+    articulation: &Articulation,
+    physx: &mut PhysxResources,
+    material_storage: &AssetStorage<PhysicsMaterial>,
+    layers: &CollisionLayers,
+    hierarchy: &ParentHierarchy,
+    links: &ReadStorage<'_, ArticulationLink>,
+    colliders: &ReadStorage<'_, Collider>,
+    transforms: &WriteStorage<'_, Transform>,
+) -> BuiltArticulation {
+    let PhysxResources { physics, scene, cooker, material, materials, .. } = physx;
+    let physics = physics.as_mut().unwrap();
+    let cooker = cooker.as_ref().unwrap();
+
+    let px_articulation = PxPhysics_createArticulationReducedCoordinate_mut(physics.get_raw_mut());
+    PxArticulationReducedCoordinate_setArticulationFlag_mut(px_articulation, PxArticulationFlag::eFIX_BASE, articulation.fix_base);
+    let base = px_articulation as *mut PxArticulationBase;
+
+    // A root below other entities, like a skeleton inside a glTF scene, starts at its world pose
+    let root_local = *transforms.get(root).unwrap().isometry();
+    let root_pose = match hierarchy.parent(root).and_then(|parent| transforms.get(parent)) {
+        Some(parent) => global_isometry(parent) * root_local,
+        None => root_local,
+    };
+    let root_link = PxArticulationBase_createLink_mut(base, null_mut(), &isometry_to_px(&root_pose));
+    if let Some(collider) = supported_collider(colliders.get(root), BodyType::Dynamic) {
+        let material = materials.resolve(Some(collider), material_storage, physics, *material).unwrap_or(*material);
+        collider.attach_to(root_link as *mut PxRigidActor, physics, cooker, material, layers);
+    }
+    PxRigidBodyExt_updateMassAndInertia_mut_1(root_link as *mut PxRigidBody, articulation.density, null(), false);
+
+    let mut built_links = vec![BuiltLink {
+        entity: root,
+        link: root_link as usize,
+        parent: None,
+    }];
+
+    // Breadth first, so every link is created after its parent
+    let mut open = VecDeque::new();
+    open.push_back((root, root_link, root_pose));
+    while let Some((parent, parent_link, parent_pose)) = open.pop_front() {
+        for &child in hierarchy.children(parent) {
+            let (link, transform) = match (links.get(child), transforms.get(child)) {
+                (Some(link), Some(transform)) => (link, transform),
+                _ => continue,
+            };
+
+            let local = *transform.isometry();
+            let pose = parent_pose * local;
+            let px_link = PxArticulationBase_createLink_mut(base, parent_link, &isometry_to_px(&pose));
+            if let Some(collider) = supported_collider(colliders.get(child), BodyType::Dynamic) {
+                let material = materials.resolve(Some(collider), material_storage, physics, *material).unwrap_or(*material);
+                collider.attach_to(px_link as *mut PxRigidActor, physics, cooker, material, layers);
+            }
+            PxRigidBodyExt_updateMassAndInertia_mut_1(px_link as *mut PxRigidBody, link.density, null(), false);
+
+            let joint = PxArticulationLink_getInboundJoint(px_link) as *mut PxArticulationJointReducedCoordinate;
+            let joint_type = match link.joint.kind {
+                ArticulationJointKind::Fixed => PxArticulationJointType::eFIX,
+                ArticulationJointKind::Revolute => PxArticulationJointType::eREVOLUTE,
+                ArticulationJointKind::Prismatic => PxArticulationJointType::ePRISMATIC,
+                ArticulationJointKind::Spherical => PxArticulationJointType::eSPHERICAL,
+            };
+            PxArticulationJointReducedCoordinate_setJointType_mut(joint, joint_type);
+            let parent_frame = link.joint.parent_frame.unwrap_or(local * link.joint.child_frame);
+            PxArticulationJointBase_setParentPose_mut(joint as *mut PxArticulationJointBase, &isometry_to_px(&parent_frame));
+            PxArticulationJointBase_setChildPose_mut(joint as *mut PxArticulationJointBase, &isometry_to_px(&link.joint.child_frame));
+            link.joint.apply_settings(joint);
+
+            built_links.push(BuiltLink {
+                entity: child,
+                link: px_link as usize,
+                parent: Some(parent_link as usize),
+            });
+            open.push_back((child, px_link, pose));
+        }
+    }
+
+    PxScene_addArticulation_mut(scene.get_raw_mut(), base);
+
+    BuiltArticulation {
+        root,
+        articulation: px_articulation as usize,
+        links: built_links,
+    }
+}
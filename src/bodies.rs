@@ -0,0 +1,240 @@
+use amethyst::{
+    derive::SystemDesc,
+    ecs::{
+        storage::ComponentEvent, world::Index, Entities, Entity, Read, ReadStorage, ReaderId,
+        System, SystemData, Write, WriteStorage,
+    },
+};
+use physx::prelude::*;
+use physx_sys::{PxActor, PxActor_release_mut};
+use std::collections::{HashMap, HashSet};
+
+use crate::articulation::PhysicsArticulations;
+use crate::collider::Collider;
+use crate::interpolation::PhysicsPose;
+use crate::resources::PhysXRef;
+use crate::rigid_body::{BodyType, RigidBody};
+
+/// Bidirectional map between entities and the PhysX actors created for them.
+///
+/// Every entity owns at most one actor, either the actor of its `RigidBody`, the static actor
+/// of a lone `Collider` or the kinematic actor of a `CharacterController`. The map also remembers which `BodyType` the actor was built as.
+///
+/// Articulation links are registered as `BodyType::Dynamic` but are `PxArticulationLink`s rather
+/// than `PxRigidDynamic`s, so only `PxRigidBody` functions are safe on dynamic actors.
+///
+/// Every inserted actor gets a new generation. PhysX may reuse the address of a released actor,
+/// so anything bound to an actor, like a joint, compares generations to notice a rebuilt actor.
+#[derive(Default)]
+pub struct PhysicsBodies {
+    by_index: HashMap<Index, BodyEntry>,
+    by_actor: HashMap<usize, Entity>,
+    next_generation: u64,
+}
+
+struct BodyEntry {
+    entity: Entity,
+    handle: BodyHandle,
+    body_type: BodyType,
+    generation: u64,
+}
+
+impl PhysicsBodies {
+    pub fn insert(&mut self, entity: Entity, handle: BodyHandle, body_type: BodyType) {
+        let actor = handle.0;
+        self.next_generation += 1;
+        let entry = BodyEntry { entity, handle, body_type, generation: self.next_generation };
+        if let Some(old) = self.by_index.insert(entity.id(), entry) {
+            self.by_actor.remove(&old.handle.0);
+        }
+        self.by_actor.insert(actor, entity);
+    }
+
+    pub fn remove(&mut self, entity: Entity) -> Option<BodyHandle> {
+        match self.by_index.get(&entity.id()) {
+            Some(entry) if entry.entity == entity => self.remove_index(entity.id()).map(|(_, handle)| handle),
+            _ => None,
+        }
+    }
+
+    /// The entity whose actor is stored under `index`, which may be a deleted entity whose index
+    /// has been reused.
+    pub(crate) fn owner(&self, index: Index) -> Option<Entity> {
+        self.by_index.get(&index).map(|entry| entry.entity)
+    }
+
+    pub(crate) fn remove_index(&mut self, index: Index) -> Option<(Entity, BodyHandle)> {
+        let entry = self.by_index.remove(&index)?;
+        self.by_actor.remove(&entry.handle.0);
+        Some((entry.entity, entry.handle))
+    }
+
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.actor(entity).is_some()
+    }
+
+    pub fn actor(&self, entity: Entity) -> Option<&BodyHandle> {
+        self.get(entity).map(|(handle, _)| handle)
+    }
+
+    pub fn body_type(&self, entity: Entity) -> Option<BodyType> {
+        self.get(entity).map(|(_, body_type)| body_type)
+    }
+
+    pub fn get(&self, entity: Entity) -> Option<(&BodyHandle, BodyType)> {
+        self.entry(entity).map(|entry| (&entry.handle, entry.body_type))
+    }
+
+    /// Generation of the entity's current actor, changes whenever the actor is rebuilt.
+    pub fn generation(&self, entity: Entity) -> Option<u64> {
+        self.entry(entity).map(|entry| entry.generation)
+    }
+
+    fn entry(&self, entity: Entity) -> Option<&BodyEntry> {
+        self.by_index.get(&entity.id()).filter(|entry| entry.entity == entity)
+    }
+
+    pub fn entity(&self, handle: &BodyHandle) -> Option<Entity> {
+        self.entity_by_ptr(handle.0)
+    }
+
+    /// Looks up the entity owning a raw `PxRigidActor` pointer, as handed out by PhysX callbacks.
+    pub fn entity_by_ptr(&self, actor: usize) -> Option<Entity> {
+        self.by_actor.get(&actor).copied()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Entity, &BodyHandle)> {
+        self.by_index.values().map(|entry| (entry.entity, &entry.handle))
+    }
+}
+
+/// Releases the actors of entities that lost their `RigidBody` or `Collider`, including deleted
+/// entities. Runs before the PhysX step so despawned bodies never take part in the simulation.
+///
+/// An actor whose `Collider` was inserted, changed or removed is released as well, and
+/// `RigidBodySyncSystem` builds it again with the new shape.
+#[derive(SystemDesc)]
+pub struct PhysicsCleanupSystem {
+    #[system_desc(flagged_storage_reader(RigidBody))]
+    rigid_body_reader: ReaderId<ComponentEvent>,
+    #[system_desc(flagged_storage_reader(Collider))]
+    collider_reader: ReaderId<ComponentEvent>,
+}
+
+impl PhysicsCleanupSystem {
+    pub fn new(rigid_body_reader: ReaderId<ComponentEvent>, collider_reader: ReaderId<ComponentEvent>) -> Self {
+        PhysicsCleanupSystem { rigid_body_reader, collider_reader }
+    }
+}
+
+impl<'a> System<'a> for PhysicsCleanupSystem {
+    type SystemData = (
+        Entities<'a>,
+        Write<'a, PhysXRef>,
+        Write<'a, PhysicsBodies>,
+        Read<'a, PhysicsArticulations>,
+        ReadStorage<'a, RigidBody>,
+        ReadStorage<'a, Collider>,
+        WriteStorage<'a, PhysicsPose>,
+    );
+
+    fn run(&mut self, (entities, physx, mut bodies, articulations, rigid_bodies, colliders, mut poses): Self::SystemData) {
+        let mut changed = HashSet::new();
+        for event in rigid_bodies.channel().read(&mut self.rigid_body_reader) {
+            match event {
+                ComponentEvent::Inserted(index) | ComponentEvent::Removed(index) => {
+                    changed.insert(*index);
+                }
+                ComponentEvent::Modified(_) => {}
+            }
+        }
+        let mut reshaped = HashSet::new();
+        for event in colliders.channel().read(&mut self.collider_reader) {
+            match event {
+                ComponentEvent::Inserted(index) | ComponentEvent::Modified(index) | ComponentEvent::Removed(index) => {
+                    reshaped.insert(*index);
+                }
+            }
+        }
+        changed.extend(reshaped.iter().copied());
+
+        if changed.is_empty() {
+            return;
+        }
+
+        let _physx_lock = physx.0.as_ref().unwrap().lock().unwrap();
+        for index in changed {
+            // Articulation links are released together with their articulation
+            if articulations.owns_index(index) {
+                continue;
+            }
+            let owner = match bodies.owner(index) {
+                Some(owner) => owner,
+                None => continue,
+            };
+
+            // The index may belong to a new entity by now, so look at the entity that owns the actor
+            let release = !entities.is_alive(owner)
+                || (!rigid_bodies.contains(owner) && !colliders.contains(owner))
+                || reshaped.contains(&index);
+            if !release {
+                continue;
+            }
+
+            if let Some((entity, handle)) = bodies.remove_index(index) {
+                poses.remove(entity);
+                unsafe {
+                    PxActor_release_mut(handle.0 as *mut PxActor);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use amethyst::ecs::{Builder, World, WorldExt};
+
+    #[test]
+    fn maps_entities_and_actors_both_ways() {
+        let mut world = World::new();
+        let entity = world.create_entity().build();
+
+        let mut bodies = PhysicsBodies::default();
+        bodies.insert(entity, BodyHandle(10), BodyType::Dynamic);
+        assert_eq!(bodies.actor(entity).map(|handle| handle.0), Some(10));
+        assert_eq!(bodies.body_type(entity), Some(BodyType::Dynamic));
+        assert_eq!(bodies.entity_by_ptr(10), Some(entity));
+
+        // Replacing the actor forgets the old one, even at the same address
+        let generation = bodies.generation(entity);
+        bodies.insert(entity, BodyHandle(10), BodyType::Dynamic);
+        assert_ne!(bodies.generation(entity), generation);
+        bodies.insert(entity, BodyHandle(20), BodyType::Static);
+        assert_eq!(bodies.entity_by_ptr(10), None);
+        assert_eq!(bodies.entity_by_ptr(20), Some(entity));
+
+        assert_eq!(bodies.remove(entity).map(|handle| handle.0), Some(20));
+        assert!(!bodies.contains(entity));
+        assert_eq!(bodies.entity_by_ptr(20), None);
+    }
+
+    #[test]
+    fn ignores_new_entities_on_a_reused_index() {
+        let mut world = World::new();
+        let old = world.create_entity().build();
+        let mut bodies = PhysicsBodies::default();
+        bodies.insert(old, BodyHandle(10), BodyType::Dynamic);
+
+        world.delete_entity(old).unwrap();
+        world.maintain();
+        let new = world.create_entity().build();
+        assert_eq!(new.id(), old.id());
+
+        assert!(!bodies.contains(new));
+        assert!(bodies.remove(new).is_none());
+        assert_eq!(bodies.owner(new.id()), Some(old));
+        assert_eq!(bodies.remove_index(new.id()).map(|(entity, handle)| (entity, handle.0)), Some((old, 10)));
+    }
+}
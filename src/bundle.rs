@@ -0,0 +1,107 @@
+use amethyst::{
+    assets::Processor,
+    core::{SystemBundle, SystemDesc},
+    ecs::{DispatcherBuilder, World},
+    Error,
+};
+
+use crate::articulation::ArticulationSystemDesc;
+use crate::bodies::PhysicsCleanupSystemDesc;
+use crate::character::CharacterControllerSystemDesc;
+use crate::config::PhysicsConfig;
+use crate::debug_render::PhysXDebugRenderSystem;
+use crate::events::PhysicsEventSystem;
+use crate::gltf_collider::{GltfColliderSystem, GltfColliders};
+use crate::interpolation::PhysicsInterpolationSystem;
+use crate::layers::CollisionLayers;
+use crate::material::{PhysicsMaterial, PhysicsMaterialSystem};
+use crate::joint::JointSystemDesc;
+use crate::physics_time::PhysicsTime;
+use crate::physx_system::PhysXSystem;
+use crate::ragdoll::RagdollSystem;
+use crate::resources::{PhysXRef, PhysxResources};
+use crate::rigid_body::RigidBodySyncSystem;
+
+/// Creates the PhysX resources and registers the physics systems.
+///
+/// Add it before the `TransformBundle` and make the transform system depend on
+/// `PhysicsBundle::INTERPOLATION_SYSTEM`, so synced poses are propagated in the same frame:
+///
+/// ```ignore
+/// GameDataBuilder::default()
+///     .with_bundle(PhysicsBundle::new(PhysicsConfig::default()))?
+///     .with_bundle(TransformBundle::new().with_dep(&[PhysicsBundle::INTERPOLATION_SYSTEM]))?
+/// ```
+pub struct PhysicsBundle {
+    config: PhysicsConfig,
+    collision_layers: CollisionLayers,
+    debug_render: bool,
+}
+
+impl PhysicsBundle {
+    pub const CLEANUP_SYSTEM: &'static str = "physics_cleanup";
+    pub const STEP_SYSTEM: &'static str = "physx_step";
+    pub const MATERIAL_PROCESSOR: &'static str = "physics_material_processor";
+    pub const MATERIAL_SYSTEM: &'static str = "physics_materials";
+    pub const GLTF_COLLIDER_PROCESSOR: &'static str = "gltf_collider_processor";
+    pub const GLTF_COLLIDER_SYSTEM: &'static str = "gltf_colliders";
+    pub const RAGDOLL_SYSTEM: &'static str = "physics_ragdolls";
+    pub const ARTICULATION_SYSTEM: &'static str = "physics_articulations";
+    pub const SYNC_SYSTEM: &'static str = "rigid_body_sync";
+    pub const EVENT_SYSTEM: &'static str = "physics_events";
+    pub const JOINT_SYSTEM: &'static str = "physics_joints";
+    pub const CHARACTER_SYSTEM: &'static str = "character_controllers";
+    pub const INTERPOLATION_SYSTEM: &'static str = "physics_interpolation";
+    pub const DEBUG_RENDER_SYSTEM: &'static str = "physx_debug_render";
+
+    pub fn new(config: PhysicsConfig) -> Self {
+        PhysicsBundle {
+            config,
+            collision_layers: CollisionLayers::default(),
+            debug_render: true,
+        }
+    }
+
+    /// Layers for the `Collider::layer` names, without them every shape is on `"default"`.
+    pub fn with_collision_layers(mut self, collision_layers: CollisionLayers) -> Self {
+        self.collision_layers = collision_layers;
+        self
+    }
+
+    /// Skips registering the debug render system, for games without `DebugLines` or imgui.
+    pub fn without_debug_render(mut self) -> Self {
+        self.debug_render = false;
+        self
+    }
+}
+
+impl<'a, 'b> SystemBundle<'a, 'b> for PhysicsBundle {
+    fn build(self, world: &mut World, builder: &mut DispatcherBuilder<'a, 'b>) -> Result<(), Error> {
+        world.insert(PhysXRef::new(PhysxResources::new(&self.config)));
+        world.insert(PhysicsTime::new(self.config.fixed_timestep, self.config.max_substeps));
+        world.insert(self.collision_layers);
+
+        builder.add(PhysicsCleanupSystemDesc::default().build(world), Self::CLEANUP_SYSTEM, &[]);
+        builder.add(Processor::<PhysicsMaterial>::new(), Self::MATERIAL_PROCESSOR, &[]);
+        builder.add(PhysicsMaterialSystem, Self::MATERIAL_SYSTEM, &[Self::MATERIAL_PROCESSOR]);
+        builder.add(PhysXSystem, Self::STEP_SYSTEM, &[Self::CLEANUP_SYSTEM, Self::MATERIAL_SYSTEM]);
+        builder.add(Processor::<GltfColliders>::new(), Self::GLTF_COLLIDER_PROCESSOR, &[]);
+        builder.add(GltfColliderSystem::default(), Self::GLTF_COLLIDER_SYSTEM, &[Self::GLTF_COLLIDER_PROCESSOR]);
+        builder.add(RagdollSystem, Self::RAGDOLL_SYSTEM, &[Self::CLEANUP_SYSTEM]);
+        builder.add(ArticulationSystemDesc::default().build(world), Self::ARTICULATION_SYSTEM, &[Self::STEP_SYSTEM, Self::RAGDOLL_SYSTEM]);
+        builder.add(
+            RigidBodySyncSystem,
+            Self::SYNC_SYSTEM,
+            &[Self::STEP_SYSTEM, Self::ARTICULATION_SYSTEM, Self::GLTF_COLLIDER_SYSTEM],
+        );
+        builder.add(JointSystemDesc::default().build(world), Self::JOINT_SYSTEM, &[Self::SYNC_SYSTEM]);
+        builder.add(PhysicsEventSystem, Self::EVENT_SYSTEM, &[Self::STEP_SYSTEM]);
+        builder.add(CharacterControllerSystemDesc::default().build(world), Self::CHARACTER_SYSTEM, &[Self::SYNC_SYSTEM]);
+        builder.add(PhysicsInterpolationSystem, Self::INTERPOLATION_SYSTEM, &[Self::SYNC_SYSTEM, Self::CHARACTER_SYSTEM]);
+        if self.debug_render {
+            builder.add(PhysXDebugRenderSystem, Self::DEBUG_RENDER_SYSTEM, &[Self::STEP_SYSTEM]);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,436 @@
+use amethyst::{
+    core::{
+        math::{Isometry3, Point3, Vector2, Vector3},
+        transform::Transform,
+    },
+    derive::SystemDesc,
+    ecs::{
+        hibitset::BitSetLike, storage::ComponentEvent, world::Index, Component, DenseVecStorage,
+        Entities, Entity, FlaggedStorage, Join, NullStorage, Read, ReadStorage, ReaderId, System,
+        SystemData, Write, WriteStorage,
+    },
+    input::{BindingTypes, InputHandler},
+    shrev::EventChannel,
+};
+use physx::prelude::*;
+use physx_sys::{
+    PxCapsuleClimbingMode, PxCapsuleControllerDesc_delete, PxCapsuleControllerDesc_isValid,
+    PxCapsuleControllerDesc_new_alloc, PxCapsuleController, PxCapsuleController_setHeight_mut,
+    PxCapsuleController_setRadius_mut, PxController, PxControllerCollisionFlag, PxControllerDesc,
+    PxControllerFilters_new, PxControllerManager_createController_mut,
+    PxControllerNonWalkableMode, PxController_getActor, PxController_getPosition,
+    PxController_move_mut, PxController_release_mut, PxController_setContactOffset_mut,
+    PxController_setSlopeLimit_mut, PxController_setStepOffset_mut, PxExtendedVec3, PxForceMode, PxRigidActor,
+    PxRigidActor_getGlobalPose, PxRigidBody, PxRigidBodyExt_addForceAtPos, PxScene_getGravity,
+    PxVec3,
+};
+use std::collections::{HashMap, HashSet};
+use std::ops::DerefMut;
+use std::ptr::{null, null_mut};
+
+use crate::bodies::PhysicsBodies;
+use crate::events::CharacterHit;
+use crate::physics_time::PhysicsTime;
+use crate::resources::{PhysXRef, PhysxResources};
+use crate::rigid_body::BodyType;
+use crate::transform_conv::px_to_isometry;
+
+/// Capsule shaped character moved by the PhysX controller manager instead of the simulation.
+///
+/// Movement collides and slides along the scene, climbs steps up to `step_offset` and slopes up to
+/// `slope_limit`. The capsule is upright along Y and the entity's `Transform` follows its center.
+///
+/// Dynamic bodies in the way are pushed with `push_force`. Standing on a kinematic `RigidBody`
+/// carries the character along, and it keeps the platform's velocity when it jumps or walks off.
+/// Changes to the capsule settings are applied to the live controller.
+#[derive(Clone, Debug)]
+pub struct CharacterController {
+    pub radius: f32,
+    /// Height of the cylindrical part, the full capsule is `height + 2 * radius` tall.
+    pub height: f32,
+    pub step_offset: f32,
+    /// Steepest walkable slope in radians.
+    pub slope_limit: f32,
+    pub contact_offset: f32,
+    /// Horizontal speed in meters per second at full input.
+    pub speed: f32,
+    /// Upwards velocity a jump starts with.
+    pub jump_speed: f32,
+    /// Force applied to dynamic bodies the character walks into.
+    pub push_force: f32,
+    /// Desired horizontal movement on the XZ plane, scaled by `speed`.
+    pub movement: Vector2<f32>,
+    pub(crate) vertical_velocity: f32,
+    pub(crate) jump_requested: bool,
+    pub(crate) grounded: bool,
+    /// Kinematic actor the character stands on and its pose after the last move.
+    platform: Option<(usize, Isometry3<f32>)>,
+    /// Velocity inherited from the last platform, kept while airborne.
+    platform_velocity: Vector3<f32>,
+}
+
+impl Default for CharacterController {
+    fn default() -> Self {
+        CharacterController {
+            radius: 0.5,
+            height: 1.0,
+            step_offset: 0.3,
+            slope_limit: std::f32::consts::FRAC_PI_4,
+            contact_offset: 0.05,
+            speed: 5.0,
+            jump_speed: 6.0,
+            push_force: 200.0,
+            movement: Vector2::zeros(),
+            vertical_velocity: 0.0,
+            jump_requested: false,
+            grounded: false,
+            platform: None,
+            platform_velocity: Vector3::zeros(),
+        }
+    }
+}
+
+impl CharacterController {
+    pub fn new(radius: f32, height: f32) -> Self {
+        CharacterController {
+            radius,
+            height,
+            ..Default::default()
+        }
+    }
+
+    /// Jumps on the next update if the character is standing on something.
+    pub fn jump(&mut self) {
+        self.jump_requested = true;
+    }
+
+    /// Whether the last move ended touching ground below the character.
+    pub fn is_grounded(&self) -> bool {
+        self.grounded
+    }
+
+    unsafe fn create(&self, physx: &mut PhysxResources, transform: &Transform) -> *mut PxController {
+        let translation = transform.translation();
+        let desc = PxCapsuleControllerDesc_new_alloc();
+        (*desc).radius = self.radius;
+        (*desc).height = self.height;
+        (*desc).stepOffset = self.step_offset;
+        (*desc).slopeLimit = self.slope_limit.cos();
+        (*desc).contactOffset = self.contact_offset;
+        (*desc).climbingMode = PxCapsuleClimbingMode::eCONSTRAINED;
+        (*desc).nonWalkableMode = PxControllerNonWalkableMode::ePREVENT_CLIMBING_AND_FORCE_SLIDING;
+        (*desc).upDirection = PxVec3 { x: 0.0, y: 1.0, z: 0.0 };
+        (*desc).position = PxExtendedVec3 {
+            x: translation.x as f64,
+            y: translation.y as f64,
+            z: translation.z as f64,
+        };
+        (*desc).material = physx.material;
+        (*desc).reportCallback = physx.controller_hit_report;
+
+        let controller = if PxCapsuleControllerDesc_isValid(desc) {
+            PxControllerManager_createController_mut(physx.controller_manager, desc as *mut PxControllerDesc)
+        } else {
+            null_mut()
+        };
+        PxCapsuleControllerDesc_delete(desc);
+        controller
+    }
+}
+
+impl Component for CharacterController {
+    type Storage = FlaggedStorage<Self, DenseVecStorage<Self>>;
+}
+
+/// Marks the character controllers `CharacterInputSystem` drives.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PlayerCharacter;
+
+impl Component for PlayerCharacter {
+    type Storage = NullStorage<Self>;
+}
+
+/// Capsule settings a PhysX controller was created or last updated with.
+#[derive(Clone, Copy, PartialEq)]
+struct ControllerShape {
+    radius: f32,
+    height: f32,
+    step_offset: f32,
+    slope_limit: f32,
+    contact_offset: f32,
+}
+
+impl ControllerShape {
+    fn of(character: &CharacterController) -> Self {
+        ControllerShape {
+            radius: character.radius,
+            height: character.height,
+            step_offset: character.step_offset,
+            slope_limit: character.slope_limit,
+            contact_offset: character.contact_offset,
+        }
+    }
+
+    unsafe fn apply(&self, controller: *mut PxController) {
+        PxCapsuleController_setRadius_mut(controller as *mut PxCapsuleController, self.radius);
+        PxCapsuleController_setHeight_mut(controller as *mut PxCapsuleController, self.height);
+        PxController_setStepOffset_mut(controller, self.step_offset);
+        PxController_setSlopeLimit_mut(controller, self.slope_limit.cos());
+        PxController_setContactOffset_mut(controller, self.contact_offset);
+    }
+}
+
+struct ControllerEntry {
+    entity: Entity,
+    controller: usize,
+    shape: ControllerShape,
+}
+
+/// Map between `CharacterController` entities and their PhysX controllers.
+#[derive(Default)]
+pub struct PhysicsControllers {
+    by_index: HashMap<Index, ControllerEntry>,
+}
+
+impl PhysicsControllers {
+    pub fn controller(&self, entity: Entity) -> Option<*mut PxController> {
+        match self.by_index.get(&entity.id()) {
+            Some(entry) if entry.entity == entity => Some(entry.controller as *mut PxController),
+            _ => None,
+        }
+    }
+
+    unsafe fn release_index(&mut self, index: Index, bodies: &mut PhysicsBodies) {
+        if let Some(entry) = self.by_index.remove(&index) {
+            // The index may already belong to the body of a new entity
+            bodies.remove(entry.entity);
+            PxController_release_mut(entry.controller as *mut PxController);
+        }
+    }
+}
+
+/// Creates PhysX controllers for `CharacterController` components, moves them by their desired
+/// movement, jumps, gravity and platforms, and writes the resulting position to `Transform`.
+/// Pushes the dynamic bodies the moves touch and publishes every touched shape as a `CharacterHit`.
+///
+/// The controller's kinematic actor is registered in `PhysicsBodies`, so contacts and queries map
+/// back to the character entity.
+#[derive(SystemDesc)]
+pub struct CharacterControllerSystem {
+    #[system_desc(flagged_storage_reader(CharacterController))]
+    controller_reader: ReaderId<ComponentEvent>,
+}
+
+impl CharacterControllerSystem {
+    pub fn new(controller_reader: ReaderId<ComponentEvent>) -> Self {
+        CharacterControllerSystem { controller_reader }
+    }
+}
+
+impl<'a> System<'a> for CharacterControllerSystem {
+    type SystemData = (
+        Entities<'a>,
+        Read<'a, PhysicsTime>,
+        Write<'a, PhysXRef>,
+        Write<'a, PhysicsBodies>,
+        Write<'a, PhysicsControllers>,
+        WriteStorage<'a, CharacterController>,
+        WriteStorage<'a, Transform>,
+        Write<'a, EventChannel<CharacterHit>>,
+    );
+
+    fn run(
+        &mut self,
+        (entities, physics_time, physx, mut bodies, mut controllers, mut characters, mut transforms, mut hit_events): Self::SystemData,
+    ) {
+        let mut physx_lock = physx.0.as_ref().unwrap().lock().unwrap();
+        let physx_ref = physx_lock.deref_mut();
+
+        let mut removed = HashSet::new();
+        for event in characters.channel().read(&mut self.controller_reader) {
+            if let ComponentEvent::Removed(index) = event {
+                removed.insert(*index);
+            }
+        }
+        for index in removed {
+            let owner = controllers.by_index.get(&index).map(|entry| entry.entity);
+            if owner.map_or(false, |owner| !characters.contains(owner)) {
+                unsafe { controllers.release_index(index, &mut bodies) };
+            }
+        }
+
+        // Move by the time the scene was stepped this frame, so characters keep pace with the bodies
+        let delta = physics_time.steps_last_frame() as f32 * physics_time.fixed_timestep;
+        let gravity = unsafe { PxScene_getGravity(physx_ref.scene.get_raw()) }.y;
+        let filters = unsafe { PxControllerFilters_new(null(), null_mut(), null_mut()) };
+
+        // Restricted, so updating the movement state doesn't flag the components as modified
+        for (entity, mut character, transform) in (&entities, &mut characters.restrict_mut(), &mut transforms).join() {
+            let character = character.get_mut_unchecked();
+            let shape = ControllerShape::of(character);
+            let controller = match controllers.controller(entity) {
+                Some(controller) => {
+                    let entry = controllers.by_index.get_mut(&entity.id()).unwrap();
+                    if entry.shape != shape {
+                        unsafe { shape.apply(controller) };
+                        entry.shape = shape;
+                    }
+                    controller
+                }
+                None => unsafe {
+                    // A controller left on this index by a deleted character
+                    controllers.release_index(entity.id(), &mut bodies);
+
+                    let controller = character.create(physx_ref, transform);
+                    if controller.is_null() {
+                        continue;
+                    }
+                    controllers.by_index.insert(entity.id(), ControllerEntry { entity, controller: controller as usize, shape });
+                    let actor = PxController_getActor(controller);
+                    bodies.insert(entity, BodyHandle(actor as usize), BodyType::Kinematic);
+                    controller
+                },
+            };
+            if delta == 0.0 {
+                continue;
+            }
+
+            // Follow the platform's motion since the last move, or keep its velocity in the air
+            let position = unsafe { extended_to_point(&*PxController_getPosition(controller)) };
+            let carried = match character.platform.take() {
+                Some((actor, last_pose)) if bodies.entity_by_ptr(actor).is_some() => {
+                    let pose = unsafe { px_to_isometry(&PxRigidActor_getGlobalPose(actor as *const PxRigidActor)) };
+                    let carried = pose.transform_point(&last_pose.inverse_transform_point(&position)) - position;
+                    character.platform_velocity = carried / delta;
+                    carried
+                }
+                _ => {
+                    // Off the platform its vertical velocity becomes our own, gravity takes over from there
+                    character.vertical_velocity += character.platform_velocity.y;
+                    character.platform_velocity.y = 0.0;
+                    if character.grounded {
+                        character.platform_velocity = Vector3::zeros();
+                    }
+                    character.platform_velocity * delta
+                }
+            };
+
+            if character.grounded && character.jump_requested {
+                character.vertical_velocity = character.jump_speed;
+            } else if character.grounded && character.vertical_velocity < 0.0 {
+                // Keep pressing into the ground so slopes and steps down stay grounded
+                character.vertical_velocity = gravity * delta;
+            } else {
+                character.vertical_velocity += gravity * delta;
+            }
+            character.jump_requested = false;
+
+            let horizontal = character.movement * character.speed * delta;
+            let displacement = PxVec3 {
+                x: horizontal.x + carried.x,
+                y: character.vertical_velocity * delta + carried.y,
+                z: horizontal.y + carried.z,
+            };
+
+            physx_ref.events.controller_hits.clear();
+            unsafe {
+                let flags = PxController_move_mut(controller, &displacement, 0.001, delta, &filters, null());
+                character.grounded = flags.mBits & PxControllerCollisionFlag::eCOLLISION_DOWN as u8 != 0;
+                if flags.mBits & PxControllerCollisionFlag::eCOLLISION_UP as u8 != 0 && character.vertical_velocity > 0.0 {
+                    character.vertical_velocity = 0.0;
+                }
+
+                let position = &*PxController_getPosition(controller);
+                transform.set_translation(Vector3::new(position.x as f32, position.y as f32, position.z as f32));
+            }
+
+            let ground_normal_y = character.slope_limit.cos();
+            for hit in physx_ref.events.controller_hits.drain(..) {
+                let other = match bodies.entity_by_ptr(hit.actor) {
+                    Some(other) => other,
+                    None => continue,
+                };
+                let standing_on = hit.normal.y >= ground_normal_y;
+
+                match bodies.body_type(other) {
+                    Some(BodyType::Kinematic) if standing_on && controllers.controller(other).is_none() => unsafe {
+                        let pose = px_to_isometry(&PxRigidActor_getGlobalPose(hit.actor as *const PxRigidActor));
+                        character.platform = Some((hit.actor, pose));
+                    },
+                    Some(BodyType::Dynamic) if !standing_on && character.push_force > 0.0 => {
+                        let push = Vector3::new(hit.direction.x, 0.0, hit.direction.z);
+                        if let Some(push) = push.try_normalize(1.0e-6) {
+                            let force = push * character.push_force;
+                            unsafe {
+                                PxRigidBodyExt_addForceAtPos(
+                                    hit.actor as *mut PxRigidBody,
+                                    &PxVec3 { x: force.x, y: force.y, z: force.z },
+                                    &PxVec3 { x: hit.position.x, y: hit.position.y, z: hit.position.z },
+                                    PxForceMode::eFORCE,
+                                    true,
+                                );
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+
+                hit_events.single_write(CharacterHit {
+                    character: entity,
+                    other,
+                    position: hit.position,
+                    normal: hit.normal,
+                    direction: hit.direction,
+                    length: hit.length,
+                });
+            }
+        }
+    }
+}
+
+fn extended_to_point(position: &PxExtendedVec3) -> Point3<f32> {
+    Point3::new(position.x as f32, position.y as f32, position.z as f32)
+}
+
+/// Drives every `PlayerCharacter` from two input axes for movement along world X and Z and an
+/// action to jump.
+pub struct CharacterInputSystem<B: BindingTypes> {
+    pub x_axis: B::Axis,
+    pub z_axis: B::Axis,
+    pub jump_action: B::Action,
+}
+
+impl<B: BindingTypes> CharacterInputSystem<B> {
+    pub fn new(x_axis: B::Axis, z_axis: B::Axis, jump_action: B::Action) -> Self {
+        CharacterInputSystem {
+            x_axis,
+            z_axis,
+            jump_action,
+        }
+    }
+}
+
+impl<'a, B: BindingTypes> System<'a> for CharacterInputSystem<B> {
+    type SystemData = (
+        Read<'a, InputHandler<B>>,
+        ReadStorage<'a, PlayerCharacter>,
+        WriteStorage<'a, CharacterController>,
+    );
+
+    fn run(&mut self, (input, players, mut characters): Self::SystemData) {
+        let x = input.axis_value(&self.x_axis).unwrap_or(0.0);
+        let z = input.axis_value(&self.z_axis).unwrap_or(0.0);
+        let mut movement = Vector2::new(x, z);
+        if movement.norm_squared() > 1.0 {
+            movement.normalize_mut();
+        }
+        let jump = input.action_is_down(&self.jump_action).unwrap_or(false);
+
+        for (_, character) in (&players, &mut characters).join() {
+            character.movement = movement;
+            if jump {
+                character.jump();
+            }
+        }
+    }
+}
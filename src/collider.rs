@@ -0,0 +1,274 @@
+use amethyst::{
+    assets::Handle,
+    core::math::{UnitQuaternion, Vector3},
+    ecs::{Component, DenseVecStorage, FlaggedStorage},
+};
+use log::warn;
+use physx::prelude::*;
+use physx_sys::{
+    PxBoxGeometry_new_1, PxCapsuleGeometry_new_1, PxConvexMeshGeometryFlags,
+    PxConvexMeshGeometry_new_1, PxConvexMesh_release_mut, PxFilterData, PxGeometry, PxMaterial,
+    PxMeshGeometryFlags, PxMeshScale_new, PxPhysics_createShape_mut, PxPlaneGeometry_new, PxQuat,
+    PxRigidActor, PxRigidActor_attachShape_mut, PxShape, PxShapeFlag, PxShapeFlags,
+    PxShape_release_mut, PxShape_setLocalPose_mut, PxShape_setQueryFilterData_mut,
+    PxShape_setSimulationFilterData_mut, PxSphereGeometry_new_1, PxTransform,
+    PxTriangleMeshGeometry_new_1, PxTriangleMesh_release_mut, PxVec3,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::cooking::MeshCooker;
+use crate::events::SKIP_CONTACT_DETAILS;
+use crate::layers::CollisionLayers;
+use crate::material::PhysicsMaterial;
+use crate::rigid_body::BodyType;
+
+/// Geometry of a `Collider`, mirrors the PhysX geometry types.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum ColliderShape {
+    Sphere { radius: f32 },
+    Box { half_extents: [f32; 3] },
+    /// Capsules are aligned to the local X axis, like in PhysX.
+    Capsule { radius: f32, half_height: f32 },
+    /// Planes face the local +X axis and can only be used on static actors.
+    Plane,
+    ConvexMesh { vertices: Vec<[f32; 3]> },
+    /// Triangle meshes can't be used on dynamic actors, only on static and kinematic ones.
+    TriangleMesh { vertices: Vec<[f32; 3]>, indices: Vec<u32> },
+}
+
+/// Shape attached to the entity's PhysX actor.
+///
+/// Next to a `RigidBody` the shape is attached to its dynamic actor, on its own the collider gets a
+/// static actor at the entity's `Transform`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Collider {
+    pub shape: ColliderShape,
+    #[serde(default = "Vector3::zeros")]
+    pub offset: Vector3<f32>,
+    #[serde(default = "UnitQuaternion::identity")]
+    pub rotation: UnitQuaternion<f32>,
+    /// Turns the shape into a sensor that doesn't collide but reports `TriggerEvent`s.
+    /// Planes and triangle meshes can't be triggers.
+    #[serde(default)]
+    pub trigger: bool,
+    /// Name of the shape's layer in `CollisionLayers`, decides what it collides with and which
+    /// `QueryFilter`s find it.
+    #[serde(default = "default_layer")]
+    pub layer: String,
+    /// Reports `ContactPhase::Persist` events and contact points for the contacts of this shape.
+    /// Turning it off saves their cost, contacts with the shape then only report `Begin` and `End`
+    /// without points.
+    #[serde(default = "default_report_contacts")]
+    pub report_contacts: bool,
+    /// Surface material of the shape, the default material from `PhysicsConfig` without one.
+    /// The actor is created once the asset has loaded.
+    #[serde(skip)]
+    pub material: Option<Handle<PhysicsMaterial>>,
+}
+
+fn default_layer() -> String {
+    String::from("default")
+}
+
+fn default_report_contacts() -> bool {
+    true
+}
+
+impl Collider {
+    pub fn new(shape: ColliderShape) -> Self {
+        Collider {
+            shape,
+            offset: Vector3::zeros(),
+            rotation: UnitQuaternion::identity(),
+            trigger: false,
+            layer: default_layer(),
+            report_contacts: true,
+            material: None,
+        }
+    }
+
+    pub fn trigger(shape: ColliderShape) -> Self {
+        Collider {
+            trigger: true,
+            ..Collider::new(shape)
+        }
+    }
+
+    pub fn with_offset(mut self, offset: Vector3<f32>, rotation: UnitQuaternion<f32>) -> Self {
+        self.offset = offset;
+        self.rotation = rotation;
+        self
+    }
+
+    pub fn with_layer(mut self, layer: impl Into<String>) -> Self {
+        self.layer = layer.into();
+        self
+    }
+
+    pub fn without_contact_reports(mut self) -> Self {
+        self.report_contacts = false;
+        self
+    }
+
+    pub fn with_material(mut self, material: Handle<PhysicsMaterial>) -> Self {
+        self.material = Some(material);
+        self
+    }
+
+    /// Whether the shape can be attached to an actor of `body_type`. Planes only work on static
+    /// actors and triangle meshes on static and kinematic ones.
+    pub fn supports(&self, body_type: BodyType) -> bool {
+        match self.shape {
+            ColliderShape::Plane => body_type == BodyType::Static,
+            ColliderShape::TriangleMesh { .. } => body_type != BodyType::Dynamic,
+            _ => true,
+        }
+    }
+
+    pub fn local_pose(&self) -> PxTransform {
+        PxTransform {
+            q: PxQuat { x: self.rotation.i, y: self.rotation.j, z: self.rotation.k, w: self.rotation.w },
+            p: PxVec3 { x: self.offset.x, y: self.offset.y, z: self.offset.z },
+        }
+    }
+
+    /// Creates an exclusive shape for this collider, mesh shapes are cooked on the fly or loaded
+    /// from the mesh cache.
+    /// Returns `None` if the mesh could not be cooked or PhysX rejected the shape.
+    pub(crate) unsafe fn create_shape(
+        &self,
+        physics: &mut Physics,
+        cooker: &MeshCooker,
+        material: *mut PxMaterial,
+        layers: &CollisionLayers,
+    ) -> Option<*mut PxShape> {
+        if self.trigger {
+            if let ColliderShape::Plane | ColliderShape::TriangleMesh { .. } = self.shape {
+                warn!("Planes and triangle meshes can't be triggers, skipping the shape");
+                return None;
+            }
+        }
+
+        let flags = if self.trigger {
+            PxShapeFlags { mBits: (PxShapeFlag::eVISUALIZATION | PxShapeFlag::eTRIGGER_SHAPE) as u8 }
+        } else {
+            PxShapeFlags {
+                mBits: (PxShapeFlag::eVISUALIZATION | PxShapeFlag::eSCENE_QUERY_SHAPE | PxShapeFlag::eSIMULATION_SHAPE) as u8,
+            }
+        };
+        let physics_ptr = physics.get_raw_mut();
+        let create = |geometry: *const PxGeometry| {
+            PxPhysics_createShape_mut(physics_ptr, geometry, material, true, flags)
+        };
+
+        let shape = match &self.shape {
+            ColliderShape::Sphere { radius } => {
+                let geometry = PxSphereGeometry_new_1(*radius);
+                create(&geometry as *const _ as *const PxGeometry)
+            }
+            ColliderShape::Box { half_extents: [x, y, z] } => {
+                let geometry = PxBoxGeometry_new_1(*x, *y, *z);
+                create(&geometry as *const _ as *const PxGeometry)
+            }
+            ColliderShape::Capsule { radius, half_height } => {
+                let geometry = PxCapsuleGeometry_new_1(*radius, *half_height);
+                create(&geometry as *const _ as *const PxGeometry)
+            }
+            ColliderShape::Plane => {
+                let geometry = PxPlaneGeometry_new();
+                create(&geometry as *const _ as *const PxGeometry)
+            }
+            ColliderShape::ConvexMesh { vertices } => {
+                let mesh = cooker.create_convex_mesh(physics, vertices)?;
+                let geometry = PxConvexMeshGeometry_new_1(mesh, &PxMeshScale_new(), PxConvexMeshGeometryFlags { mBits: 0 });
+                let shape = create(&geometry as *const _ as *const PxGeometry);
+                // The shape holds its own reference to the mesh
+                PxConvexMesh_release_mut(mesh);
+                shape
+            }
+            ColliderShape::TriangleMesh { vertices, indices } => {
+                let mesh = cooker.create_triangle_mesh(physics, vertices, indices)?;
+                let geometry = PxTriangleMeshGeometry_new_1(mesh, &PxMeshScale_new(), PxMeshGeometryFlags { mBits: 0 });
+                let shape = create(&geometry as *const _ as *const PxGeometry);
+                PxTriangleMesh_release_mut(mesh);
+                shape
+            }
+        };
+        if shape.is_null() {
+            return None;
+        }
+
+        PxShape_setLocalPose_mut(shape, &self.local_pose());
+        let filter_data = layers.filter_data(&self.layer);
+        PxShape_setQueryFilterData_mut(shape, &filter_data);
+        let simulation_filter_data = PxFilterData {
+            word2: if self.report_contacts { 0 } else { SKIP_CONTACT_DETAILS },
+            ..filter_data
+        };
+        PxShape_setSimulationFilterData_mut(shape, &simulation_filter_data);
+        Some(shape)
+    }
+
+    pub(crate) unsafe fn attach_to(
+        &self,
+        actor: *mut PxRigidActor,
+        physics: &mut Physics,
+        cooker: &MeshCooker,
+        material: *mut PxMaterial,
+        layers: &CollisionLayers,
+    ) {
+        match self.create_shape(physics, cooker, material, layers) {
+            Some(shape) => {
+                PxRigidActor_attachShape_mut(actor, shape);
+                // The actor holds its own reference to the exclusive shape
+                PxShape_release_mut(shape);
+            }
+            None => warn!("Failed to create the collider shape, the actor is left without it"),
+        }
+    }
+}
+
+/// The collider to attach to an actor of `body_type`, shapes the actor can't have are left out
+/// with a warning.
+pub(crate) fn supported_collider(collider: Option<&Collider>, body_type: BodyType) -> Option<&Collider> {
+    match collider {
+        Some(collider) if !collider.supports(body_type) => {
+            warn!("Planes only work on static bodies and triangle meshes on static and kinematic ones, skipping the shape of a {:?} body", body_type);
+            None
+        }
+        collider => collider,
+    }
+}
+
+impl Component for Collider {
+    type Storage = FlaggedStorage<Self, DenseVecStorage<Self>>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn static_only_shapes_stay_off_moving_bodies() {
+        let plane = Collider::new(ColliderShape::Plane);
+        assert!(plane.supports(BodyType::Static));
+        assert!(!plane.supports(BodyType::Kinematic));
+        assert!(!plane.supports(BodyType::Dynamic));
+
+        let triangle_mesh = Collider::new(ColliderShape::TriangleMesh { vertices: Vec::new(), indices: Vec::new() });
+        assert!(triangle_mesh.supports(BodyType::Kinematic));
+        assert!(!triangle_mesh.supports(BodyType::Dynamic));
+
+        assert!(Collider::new(ColliderShape::ConvexMesh { vertices: Vec::new() }).supports(BodyType::Dynamic));
+    }
+
+    #[test]
+    fn supported_collider_skips_unsupported_shapes() {
+        let plane = Collider::new(ColliderShape::Plane);
+        let sphere = Collider::new(ColliderShape::Sphere { radius: 1.0 });
+        assert!(supported_collider(Some(&plane), BodyType::Dynamic).is_none());
+        assert!(supported_collider(Some(&plane), BodyType::Static).is_some());
+        assert!(supported_collider(Some(&sphere), BodyType::Dynamic).is_some());
+        assert!(supported_collider(None, BodyType::Dynamic).is_none());
+    }
+}
@@ -0,0 +1,129 @@
+use physx::prelude::BroadPhaseType;
+use physx_sys::PxSolverType;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Settings used by `PhysicsBundle` to create the PhysX foundation, physics and scene, usually
+/// loaded from `config/physics.ron` so the simulation can be tuned without recompiling.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PhysicsConfig {
+    pub gravity: [f32; 3],
+    /// Number of worker threads PhysX simulates the scene on.
+    pub dispatcher_threads: u32,
+    pub solver: SolverType,
+    pub broadphase: BroadphaseType,
+    /// Relative velocity below which contacts stop bouncing, in meters per second.
+    pub bounce_threshold: f32,
+    pub scene_flags: SceneFlags,
+    /// Length of a single simulation step in seconds.
+    pub fixed_timestep: f32,
+    /// Maximum number of steps simulated in one frame, the remaining time is dropped.
+    pub max_substeps: u32,
+    /// PhysX extensions are required for joints, including `GrabSystem`.
+    pub load_extensions: bool,
+    /// Enables the PhysX debug visualization rendered through `DebugLines`.
+    pub debug_visualization: bool,
+    /// Directory cooked convex and triangle meshes are cached in, relative to the application
+    /// root. `None` cooks every mesh at startup.
+    pub mesh_cache: Option<PathBuf>,
+    pub default_material: MaterialConfig,
+}
+
+impl Default for PhysicsConfig {
+    fn default() -> Self {
+        PhysicsConfig {
+            gravity: [0.0, -9.81, 0.0],
+            dispatcher_threads: 1,
+            solver: SolverType::default(),
+            broadphase: BroadphaseType::default(),
+            bounce_threshold: 0.2,
+            scene_flags: SceneFlags::default(),
+            fixed_timestep: 1.0 / 60.0,
+            max_substeps: 4,
+            load_extensions: true,
+            debug_visualization: true,
+            mesh_cache: Some(PathBuf::from("assets/cooked")),
+            default_material: MaterialConfig::default(),
+        }
+    }
+}
+
+/// Constraint solver of the scene.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SolverType {
+    /// Projected Gauss-Seidel, the PhysX default.
+    PGS,
+    /// Temporal Gauss-Seidel, converges faster on stiff joint chains like ragdolls.
+    TGS,
+}
+
+impl Default for SolverType {
+    fn default() -> Self {
+        SolverType::PGS
+    }
+}
+
+impl SolverType {
+    pub(crate) fn to_px(self) -> PxSolverType::Enum {
+        match self {
+            SolverType::PGS => PxSolverType::ePGS,
+            SolverType::TGS => PxSolverType::eTGS,
+        }
+    }
+}
+
+/// Broadphase algorithm finding the potentially touching pairs of the scene.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BroadphaseType {
+    /// Sweep and prune, good for mostly static scenes.
+    SAP,
+    /// Multi box pruning, only handles shapes inside broadphase regions added to the scene.
+    MBP,
+    /// Automatic box pruning, MBP without the regions.
+    ABP,
+}
+
+impl Default for BroadphaseType {
+    fn default() -> Self {
+        BroadphaseType::SAP
+    }
+}
+
+impl BroadphaseType {
+    pub(crate) fn to_px(self) -> BroadPhaseType {
+        match self {
+            BroadphaseType::SAP => BroadPhaseType::SweepAndPrune,
+            BroadphaseType::MBP => BroadPhaseType::MultiBoxPruning,
+            BroadphaseType::ABP => BroadPhaseType::AutomaticBoxPruning,
+        }
+    }
+}
+
+/// Scene flags that can be changed after the scene is created.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SceneFlags {
+    /// Makes PhysX track the actors that moved during a step.
+    pub active_actors: bool,
+    /// Leaves kinematic bodies out of the active actors.
+    pub exclude_kinematics_from_active_actors: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MaterialConfig {
+    pub static_friction: f32,
+    pub dynamic_friction: f32,
+    pub restitution: f32,
+}
+
+impl Default for MaterialConfig {
+    fn default() -> Self {
+        MaterialConfig {
+            static_friction: 0.5,
+            dynamic_friction: 0.5,
+            restitution: 0.6,
+        }
+    }
+}
@@ -0,0 +1,250 @@
+use log::warn;
+use physx::prelude::*;
+use physx_sys::{
+    phys_PxCreateCooking, PxAllocatorCallback, PxConvexFlag, PxConvexFlags, PxConvexMesh,
+    PxConvexMeshDesc_new, PxCooking, PxCookingParams_new, PxCooking_cookConvexMesh,
+    PxCooking_cookTriangleMesh, PxCooking_release_mut, PxDefaultMemoryInputData_delete,
+    PxDefaultMemoryInputData_new_alloc, PxDefaultMemoryOutputStream_delete,
+    PxDefaultMemoryOutputStream_getData, PxDefaultMemoryOutputStream_getSize,
+    PxDefaultMemoryOutputStream_new_alloc, PxFoundation_getAllocatorCallback_mut, PxInputStream,
+    PxOutputStream, PxPhysics, PxPhysics_createConvexMesh_mut, PxPhysics_createTriangleMesh_mut,
+    PxPhysics_getTolerancesScale, PxTriangleMesh, PxTriangleMeshDesc_new,
+};
+use std::ffi::c_void;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::ptr::null_mut;
+
+/// Start of every cached mesh file, followed by the PhysX version and `CACHE_VERSION`.
+const CACHE_MAGIC: &[u8; 4] = b"PXMC";
+/// Bump when the cooking parameters or the file layout change, to re-cook old files.
+const CACHE_VERSION: u32 = 1;
+const CACHE_HEADER_LEN: usize = 12;
+
+/// Thin wrapper around `PxCooking`, used to turn collider vertex data into PhysX meshes.
+///
+/// With a cache directory, cooked mesh streams are stored there under a hash of their vertex data
+/// and loaded instead of cooking the same mesh again. Files from another PhysX version or cache
+/// version are re-cooked and overwritten.
+pub struct MeshCooker {
+    cooking: *mut PxCooking,
+    allocator: *mut PxAllocatorCallback,
+    version: u32,
+    cache_dir: Option<PathBuf>,
+}
+
+impl MeshCooker {
+    /// Returns `None` if PhysX could not create the cooking library, for example because `version`
+    /// doesn't match the PhysX binaries.
+    pub fn new(version: u32, foundation: &mut Foundation, physics: &Physics) -> Option<MeshCooker> {
+        let (cooking, allocator) = unsafe {
+            let params = PxCookingParams_new(PxPhysics_getTolerancesScale(physics.get_raw()));
+            let cooking = phys_PxCreateCooking(version, foundation.get_raw_mut(), &params);
+            (cooking, PxFoundation_getAllocatorCallback_mut(foundation.get_raw_mut()))
+        };
+        if cooking.is_null() {
+            return None;
+        }
+
+        Some(MeshCooker { cooking, allocator, version, cache_dir: None })
+    }
+
+    /// Caches cooked meshes in `cache_dir`, which is created when the first mesh is stored.
+    pub fn with_cache(mut self, cache_dir: PathBuf) -> Self {
+        self.cache_dir = Some(cache_dir);
+        self
+    }
+
+    /// Cooks a convex hull around `vertices`. Returns `None` if PhysX could not build the hull.
+    pub fn create_convex_mesh(&self, physics: &mut Physics, vertices: &[[f32; 3]]) -> Option<*mut PxConvexMesh> {
+        let key = mesh_key(b'c', vertices, &[]);
+        unsafe {
+            let mut desc = PxConvexMeshDesc_new();
+            desc.points.count = vertices.len() as u32;
+            desc.points.stride = std::mem::size_of::<[f32; 3]>() as u32;
+            desc.points.data = vertices.as_ptr() as *const c_void;
+            desc.flags = PxConvexFlags { mBits: PxConvexFlag::eCOMPUTE_CONVEX as u16 };
+
+            self.load_or_cook(
+                key,
+                physics.get_raw_mut(),
+                |stream| PxCooking_cookConvexMesh(self.cooking, &desc, stream, null_mut()),
+                |physics, stream| PxPhysics_createConvexMesh_mut(physics, stream),
+            )
+        }
+    }
+
+    /// Cooks a triangle mesh, `indices` holds three vertex indices per triangle.
+    pub fn create_triangle_mesh(&self, physics: &mut Physics, vertices: &[[f32; 3]], indices: &[u32]) -> Option<*mut PxTriangleMesh> {
+        let key = mesh_key(b't', vertices, indices);
+        unsafe {
+            let mut desc = PxTriangleMeshDesc_new();
+            desc.points.count = vertices.len() as u32;
+            desc.points.stride = std::mem::size_of::<[f32; 3]>() as u32;
+            desc.points.data = vertices.as_ptr() as *const c_void;
+            desc.triangles.count = (indices.len() / 3) as u32;
+            desc.triangles.stride = (std::mem::size_of::<u32>() * 3) as u32;
+            desc.triangles.data = indices.as_ptr() as *const c_void;
+
+            self.load_or_cook(
+                key,
+                physics.get_raw_mut(),
+                |stream| PxCooking_cookTriangleMesh(self.cooking, &desc, stream, null_mut()),
+                |physics, stream| PxPhysics_createTriangleMesh_mut(physics, stream),
+            )
+        }
+    }
+
+    /// Creates a mesh from its cached stream, or cooks the stream with `cook` and caches it.
+    unsafe fn load_or_cook<M>(
+        &self,
+        key: u64,
+        physics: *mut PxPhysics,
+        cook: impl FnOnce(*mut PxOutputStream) -> bool,
+        create: impl Fn(*mut PxPhysics, *mut PxInputStream) -> *mut M,
+    ) -> Option<*mut M> {
+        let path = self.cache_dir.as_ref().map(|dir| dir.join(format!("{:016x}.pxmesh", key)));
+
+        if let Some(stream) = path.as_ref().and_then(|path| self.read_cached(path)) {
+            let mesh = create_from_stream(physics, &stream, &create);
+            if !mesh.is_null() {
+                return Some(mesh);
+            }
+            warn!("Failed to load the cached mesh {}, cooking it again", path.as_ref().unwrap().display());
+        }
+
+        let output = PxDefaultMemoryOutputStream_new_alloc(self.allocator);
+        let mesh = if cook(output as *mut PxOutputStream) {
+            let stream = std::slice::from_raw_parts(
+                PxDefaultMemoryOutputStream_getData(output),
+                PxDefaultMemoryOutputStream_getSize(output) as usize,
+            );
+            if let Some(path) = &path {
+                self.write_cached(path, stream);
+            }
+            create_from_stream(physics, stream, &create)
+        } else {
+            null_mut()
+        };
+        PxDefaultMemoryOutputStream_delete(output);
+
+        if mesh.is_null() { None } else { Some(mesh) }
+    }
+
+    /// The cooked stream in `path`, `None` if there is none or it was written by another version.
+    fn read_cached(&self, path: &Path) -> Option<Vec<u8>> {
+        cached_stream(fs::read(path).ok()?, self.version)
+    }
+
+    /// Writes to a temporary file first and renames it, so a crash or another process never leaves
+    /// a half written mesh behind.
+    fn write_cached(&self, path: &Path, stream: &[u8]) {
+        let mut data = Vec::with_capacity(CACHE_HEADER_LEN + stream.len());
+        data.extend_from_slice(&cache_header(self.version));
+        data.extend_from_slice(stream);
+
+        let temp_path = path.with_extension(format!("{}.tmp", std::process::id()));
+        let written = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| fs::write(&temp_path, data))
+            .and_then(|_| fs::rename(&temp_path, path));
+        if let Err(error) = written {
+            fs::remove_file(&temp_path).ok();
+            warn!("Failed to cache the cooked mesh in {}: {}", path.display(), error);
+        }
+    }
+}
+
+impl Drop for MeshCooker {
+    fn drop(&mut self) {
+        unsafe {
+            PxCooking_release_mut(self.cooking);
+        }
+    }
+}
+
+unsafe fn create_from_stream<M>(
+    physics: *mut PxPhysics,
+    stream: &[u8],
+    create: impl Fn(*mut PxPhysics, *mut PxInputStream) -> *mut M,
+) -> *mut M {
+    // The input data only reads from the buffer
+    let input = PxDefaultMemoryInputData_new_alloc(stream.as_ptr() as *mut u8, stream.len() as u32);
+    let mesh = create(physics, input as *mut PxInputStream);
+    PxDefaultMemoryInputData_delete(input);
+    mesh
+}
+
+fn cache_header(version: u32) -> [u8; CACHE_HEADER_LEN] {
+    let mut header = [0; CACHE_HEADER_LEN];
+    header[..4].copy_from_slice(CACHE_MAGIC);
+    header[4..8].copy_from_slice(&version.to_le_bytes());
+    header[8..].copy_from_slice(&CACHE_VERSION.to_le_bytes());
+    header
+}
+
+/// Strips the header off the contents of a cache file, `None` if it doesn't match `version`.
+fn cached_stream(mut data: Vec<u8>, version: u32) -> Option<Vec<u8>> {
+    if data.len() < CACHE_HEADER_LEN || data[..CACHE_HEADER_LEN] != cache_header(version) {
+        return None;
+    }
+    data.drain(..CACHE_HEADER_LEN);
+    Some(data)
+}
+
+/// FNV-1a hash of the mesh data, which unlike `DefaultHasher` stays the same between Rust
+/// versions.
+fn mesh_key(kind: u8, vertices: &[[f32; 3]], indices: &[u32]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    let mut write = |bytes: &[u8]| {
+        for byte in bytes {
+            hash = (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3);
+        }
+    };
+
+    write(&[kind]);
+    for coordinate in vertices.iter().flatten() {
+        write(&coordinate.to_bits().to_le_bytes());
+    }
+    for index in indices {
+        write(&index.to_le_bytes());
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mesh_key_is_stable() {
+        // Changing the hash orphans every cached mesh, so it's pinned here
+        assert_eq!(mesh_key(b'c', &[[0.0, 1.0, 2.0]], &[]), 0x4657_a963_3a7e_7ecb);
+    }
+
+    #[test]
+    fn mesh_key_depends_on_kind_and_indices() {
+        let vertices = [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]];
+        assert_ne!(mesh_key(b'c', &vertices, &[]), mesh_key(b't', &vertices, &[]));
+        assert_ne!(mesh_key(b't', &vertices, &[0, 1, 2]), mesh_key(b't', &vertices, &[0, 2, 1]));
+    }
+
+    #[test]
+    fn cached_stream_strips_matching_header() {
+        let mut data = cache_header(7).to_vec();
+        data.extend_from_slice(&[1, 2, 3]);
+        assert_eq!(cached_stream(data, 7), Some(vec![1, 2, 3]));
+    }
+
+    #[test]
+    fn cached_stream_rejects_other_versions() {
+        let mut data = cache_header(7).to_vec();
+        data.push(1);
+        assert_eq!(cached_stream(data.clone(), 8), None);
+
+        data[0] = b'X';
+        assert_eq!(cached_stream(data, 7), None);
+        assert_eq!(cached_stream(CACHE_MAGIC.to_vec(), 7), None);
+    }
+}
@@ -0,0 +1,192 @@
+use amethyst::{
+    core::math::Point3,
+    derive::SystemDesc,
+    ecs::{Read, ReadStorage, System, SystemData, Write},
+    renderer::debug_drawing::DebugLines,
+};
+use amethyst_imgui::imgui;
+use amethyst_imgui::imgui::im_str;
+use physx::scene::VisualizationParameter;
+use std::ops::DerefMut;
+
+use crate::color_conv;
+use crate::joint::{Joint, PhysicsJoints};
+use crate::resources::PhysXRef;
+
+/// Draws the PhysX render buffer through `DebugLines` and shows the visualization parameters in imgui,
+/// next to the force each joint applied during the last step.
+#[derive(SystemDesc)]
+pub struct PhysXDebugRenderSystem;
+impl<'a> System<'a> for PhysXDebugRenderSystem {
+    type SystemData = (
+        Write<'a, PhysXRef>,
+        Write<'a, DebugLines>, // Request DebugLines resource
+        Read<'a, PhysicsJoints>,
+        ReadStorage<'a, Joint>,
+    );
+
+    fn run(&mut self, (physx, mut debug_lines, joints, joint_storage): Self::SystemData) {
+        let mut physx_lock = physx.0.as_ref().unwrap().lock().unwrap();
+        let physx_ref = physx_lock.deref_mut();
+
+        amethyst_imgui::with(|ui| {
+            imgui::Window::new(im_str!("PhysX Visualization Parameters"))
+                .size([300f32, 650f32], imgui::Condition::Once)
+                .build(&ui, || {
+                let mut scale = physx_ref.scene.get_visualization_parameter(VisualizationParameter::Scale);
+                let mut world_axes = physx_ref.scene.get_visualization_parameter(VisualizationParameter::WorldAxes);
+                let mut body_axes = physx_ref.scene.get_visualization_parameter(VisualizationParameter::BodyAxes);
+                let mut body_mass_axes = physx_ref.scene.get_visualization_parameter(VisualizationParameter::BodyMassAxes);
+                let mut body_lin_velocity = physx_ref.scene.get_visualization_parameter(VisualizationParameter::BodyLinVelocity);
+                let mut body_ang_velocity = physx_ref.scene.get_visualization_parameter(VisualizationParameter::BodyAngVelocity);
+                let mut contact_point = physx_ref.scene.get_visualization_parameter(VisualizationParameter::ContactPoint);
+                let mut contact_normal = physx_ref.scene.get_visualization_parameter(VisualizationParameter::ContactNormal);
+                let mut contact_error = physx_ref.scene.get_visualization_parameter(VisualizationParameter::ContactError);
+                let mut contact_force = physx_ref.scene.get_visualization_parameter(VisualizationParameter::ContactForce);
+                let mut actor_axes = physx_ref.scene.get_visualization_parameter(VisualizationParameter::ActorAxes);
+                let mut collision_aabbs = physx_ref.scene.get_visualization_parameter(VisualizationParameter::CollisionAabbs);
+                let mut collision_shapes = physx_ref.scene.get_visualization_parameter(VisualizationParameter::CollisionShapes);
+                let mut collision_axes = physx_ref.scene.get_visualization_parameter(VisualizationParameter::CollisionAxes);
+                let mut collision_compounds = physx_ref.scene.get_visualization_parameter(VisualizationParameter::CollisionCompounds);
+                let mut collision_fnormals = physx_ref.scene.get_visualization_parameter(VisualizationParameter::CollisionFnormals);
+                let mut collision_edges = physx_ref.scene.get_visualization_parameter(VisualizationParameter::CollisionEdges);
+                let mut collision_static = physx_ref.scene.get_visualization_parameter(VisualizationParameter::CollisionStatic);
+                let mut collision_dynamic = physx_ref.scene.get_visualization_parameter(VisualizationParameter::CollisionDynamic);
+                let mut deprecated_collision_pairs = physx_ref.scene.get_visualization_parameter(VisualizationParameter::DeprecatedCollisionPairs);
+                let mut joint_local_frames = physx_ref.scene.get_visualization_parameter(VisualizationParameter::JointLocalFrames);
+                let mut joint_limits = physx_ref.scene.get_visualization_parameter(VisualizationParameter::JointLimits);
+                let mut cull_box = physx_ref.scene.get_visualization_parameter(VisualizationParameter::CullBox);
+                let mut mbp_regions = physx_ref.scene.get_visualization_parameter(VisualizationParameter::MbpRegions);
+                if imgui::Slider::new(im_str!("Scale"), 0f32..=1f32).build(&ui, &mut scale) {
+                    physx_ref.scene.set_visualization_parameter(VisualizationParameter::Scale, scale);
+                }
+                if imgui::Slider::new(im_str!("WorldAxes"), 0f32..=1f32).build(&ui, &mut world_axes) {
+                    physx_ref.scene.set_visualization_parameter(VisualizationParameter::WorldAxes, world_axes);
+                }
+                if imgui::Slider::new(im_str!("BodyAxes"), 0f32..=1f32).build(&ui, &mut body_axes) {
+                    physx_ref.scene.set_visualization_parameter(VisualizationParameter::BodyAxes, body_axes);
+                }
+                if imgui::Slider::new(im_str!("BodyMassAxes"), 0f32..=1f32).build(&ui, &mut body_mass_axes) {
+                    physx_ref.scene.set_visualization_parameter(VisualizationParameter::BodyMassAxes, body_mass_axes);
+                }
+                if imgui::Slider::new(im_str!("BodyLinVelocity"), 0f32..=1f32).build(&ui, &mut body_lin_velocity) {
+                    physx_ref.scene.set_visualization_parameter(VisualizationParameter::BodyLinVelocity, body_lin_velocity);
+                }
+                if imgui::Slider::new(im_str!("BodyAngVelocity"), 0f32..=1f32).build(&ui, &mut body_ang_velocity) {
+                    physx_ref.scene.set_visualization_parameter(VisualizationParameter::BodyAngVelocity, body_ang_velocity);
+                }
+                if imgui::Slider::new(im_str!("ContactPoint"), 0f32..=1f32).build(&ui, &mut contact_point) {
+                    physx_ref.scene.set_visualization_parameter(VisualizationParameter::ContactPoint, contact_point);
+                }
+                if imgui::Slider::new(im_str!("ContactNormal"), 0f32..=1f32).build(&ui, &mut contact_normal) {
+                    physx_ref.scene.set_visualization_parameter(VisualizationParameter::ContactNormal, contact_normal);
+                }
+                if imgui::Slider::new(im_str!("ContactError"), 0f32..=1f32).build(&ui, &mut contact_error) {
+                    physx_ref.scene.set_visualization_parameter(VisualizationParameter::ContactError, contact_error);
+                }
+                if imgui::Slider::new(im_str!("ContactForce"), 0f32..=1f32).build(&ui, &mut contact_force) {
+                    physx_ref.scene.set_visualization_parameter(VisualizationParameter::ContactForce, contact_force);
+                }
+                if imgui::Slider::new(im_str!("ActorAxes"), 0f32..=1f32).build(&ui, &mut actor_axes) {
+                    physx_ref.scene.set_visualization_parameter(VisualizationParameter::ActorAxes, actor_axes);
+                }
+                if imgui::Slider::new(im_str!("CollisionAabbs"), 0f32..=1f32).build(&ui, &mut collision_aabbs) {
+                    physx_ref.scene.set_visualization_parameter(VisualizationParameter::CollisionAabbs, collision_aabbs);
+                }
+                if imgui::Slider::new(im_str!("CollisionShapes"), 0f32..=1f32).build(&ui, &mut collision_shapes) {
+                    physx_ref.scene.set_visualization_parameter(VisualizationParameter::CollisionShapes, collision_shapes);
+                }
+                if imgui::Slider::new(im_str!("CollisionAxes"), 0f32..=1f32).build(&ui, &mut collision_axes) {
+                    physx_ref.scene.set_visualization_parameter(VisualizationParameter::CollisionAxes, collision_axes);
+                }
+                if imgui::Slider::new(im_str!("CollisionCompounds"), 0f32..=1f32).build(&ui, &mut collision_compounds) {
+                    physx_ref.scene.set_visualization_parameter(VisualizationParameter::CollisionCompounds, collision_compounds);
+                }
+                if imgui::Slider::new(im_str!("CollisionFnormals"), 0f32..=1f32).build(&ui, &mut collision_fnormals) {
+                    physx_ref.scene.set_visualization_parameter(VisualizationParameter::CollisionFnormals, collision_fnormals);
+                }
+                if imgui::Slider::new(im_str!("CollisionEdges"), 0f32..=1f32).build(&ui, &mut collision_edges) {
+                    physx_ref.scene.set_visualization_parameter(VisualizationParameter::CollisionEdges, collision_edges);
+                }
+                if imgui::Slider::new(im_str!("CollisionStatic"), 0f32..=1f32).build(&ui, &mut collision_static) {
+                    physx_ref.scene.set_visualization_parameter(VisualizationParameter::CollisionStatic, collision_static);
+                }
+                if imgui::Slider::new(im_str!("CollisionDynamic"), 0f32..=1f32).build(&ui, &mut collision_dynamic) {
+                    physx_ref.scene.set_visualization_parameter(VisualizationParameter::CollisionDynamic, collision_dynamic);
+                }
+                if imgui::Slider::new(im_str!("DeprecatedCollisionPairs"), 0f32..=1f32).build(&ui, &mut deprecated_collision_pairs) {
+                    physx_ref.scene.set_visualization_parameter(VisualizationParameter::DeprecatedCollisionPairs, deprecated_collision_pairs);
+                }
+                if imgui::Slider::new(im_str!("JointLocalFrames"), 0f32..=1f32).build(&ui, &mut joint_local_frames) {
+                    physx_ref.scene.set_visualization_parameter(VisualizationParameter::JointLocalFrames, joint_local_frames);
+                }
+                if imgui::Slider::new(im_str!("JointLimits"), 0f32..=1f32).build(&ui, &mut joint_limits) {
+                    physx_ref.scene.set_visualization_parameter(VisualizationParameter::JointLimits, joint_limits);
+                }
+                if imgui::Slider::new(im_str!("CullBox"), 0f32..=1f32).build(&ui, &mut cull_box) {
+                    physx_ref.scene.set_visualization_parameter(VisualizationParameter::CullBox, cull_box);
+                }
+                if imgui::Slider::new(im_str!("MbpRegions"), 0f32..=1f32).build(&ui, &mut mbp_regions) {
+                    physx_ref.scene.set_visualization_parameter(VisualizationParameter::MbpRegions, mbp_regions);
+                }
+            });
+
+            imgui::Window::new(im_str!("Joint Forces"))
+                .position([320f32, 20f32], imgui::Condition::Once)
+                .size([300f32, 300f32], imgui::Condition::Once)
+                .build(&ui, || {
+                let mut any = false;
+                for (entity, _) in joints.iter() {
+                    let (force, torque) = match joints.force(entity) {
+                        Some(force) => force,
+                        None => continue,
+                    };
+                    any = true;
+                    ui.text(format!("Joint {}", entity.id()));
+                    if joints.is_broken(entity) {
+                        ui.same_line(0.0);
+                        ui.text_colored([1.0, 0.3, 0.3, 1.0], "broken");
+                    }
+                    match joint_storage.get(entity).and_then(|joint| joint.break_force) {
+                        Some((max_force, max_torque)) => {
+                            // A break force of zero breaks on any load and has no meaningful fraction
+                            ui.text(format!("  Force: {:.1} / {:.1}", force.norm(), max_force));
+                            if max_force > 0.0 {
+                                imgui::ProgressBar::new((force.norm() / max_force).min(1.0)).build(&ui);
+                            }
+                            ui.text(format!("  Torque: {:.1} / {:.1}", torque.norm(), max_torque));
+                            if max_torque > 0.0 {
+                                imgui::ProgressBar::new((torque.norm() / max_torque).min(1.0)).build(&ui);
+                            }
+                        }
+                        None => {
+                            ui.text(format!("  Force: {:.1}", force.norm()));
+                            ui.text(format!("  Torque: {:.1}", torque.norm()));
+                        }
+                    }
+                }
+                if !any {
+                    ui.text("No joints");
+                }
+            });
+        });
+
+        let render_buffer = physx_ref.scene.get_render_buffer();
+        for point in render_buffer.get_points() {
+            let mut end_point = Point3::from(point.pos);
+            end_point.x += 0.01;
+            debug_lines.draw_line(Point3::from(point.pos), end_point, color_conv::unpack_color(point.color));
+        }
+        for line in render_buffer.get_lines() {
+            debug_lines.draw_line(
+                Point3::from(line.pos0), 
+                Point3::from(line.pos1), 
+                color_conv::unpack_color(line.color0));
+        }
+        for triangle in render_buffer.get_triangles() {
+            debug_lines.draw_line(Point3::from(triangle.pos0), Point3::from(triangle.pos1), color_conv::unpack_color(triangle.color0));
+            debug_lines.draw_line(Point3::from(triangle.pos1), Point3::from(triangle.pos2), color_conv::unpack_color(triangle.color1));
+            debug_lines.draw_line(Point3::from(triangle.pos0), Point3::from(triangle.pos2), color_conv::unpack_color(triangle.color2));
+        }
+    }
+}
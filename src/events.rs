@@ -0,0 +1,329 @@
+use amethyst::{
+    core::math::{Point3, Vector3},
+    derive::SystemDesc,
+    ecs::{Entity, Read, ReadStorage, System, SystemData, Write},
+    shrev::EventChannel,
+};
+use physx::prelude::*;
+use physx_sys::{
+    create_simulation_event_callbacks, create_user_controller_hit_report, FilterShaderCallbackInfo,
+    PxConstraintInfo, PxContactPair, PxControllerShapeHit, PxUserControllerHitReport,
+    PxContactPairHeader, PxContactPairHeaderFlag, PxContactPairPoint,
+    PxContactPair_extractContacts, PxFilterFlag, PxFilterObjectFlag, PxPairFlag,
+    PxScene_setSimulationEventCallback_mut, PxSimulationEventCallback, PxTriggerPair,
+    PxTriggerPairFlag, SimulationEventCallbackInfo,
+};
+use std::ffi::c_void;
+use std::ops::DerefMut;
+use std::ptr::null_mut;
+
+use crate::bodies::PhysicsBodies;
+use crate::joint::{Joint, PhysicsJoints};
+use crate::resources::PhysXRef;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ContactPhase {
+    /// The shapes started touching during the last step.
+    Begin,
+    /// The shapes kept touching, not reported when a collider turned off `report_contacts`.
+    Persist,
+    /// The shapes stopped touching, this event carries no contact points.
+    End,
+}
+
+#[derive(Clone, Debug)]
+pub struct ContactPoint {
+    pub position: Point3<f32>,
+    /// Points from the second entity's shape towards the first one.
+    pub normal: Vector3<f32>,
+    pub impulse: Vector3<f32>,
+    /// Negative when the shapes penetrate.
+    pub separation: f32,
+}
+
+/// Contact between the actors of two entities, published on `EventChannel<ContactEvent>`.
+#[derive(Clone, Debug)]
+pub struct ContactEvent {
+    pub entities: (Entity, Entity),
+    pub phase: ContactPhase,
+    /// Empty when a collider turned off `report_contacts`, and for `ContactPhase::End`.
+    pub points: Vec<ContactPoint>,
+}
+
+/// Overlap change between a trigger `Collider` and another shape, published on
+/// `EventChannel<TriggerEvent>`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TriggerEvent {
+    TriggerEnter { trigger: Entity, other: Entity },
+    TriggerExit { trigger: Entity, other: Entity },
+}
+
+/// A `Joint` exceeded its break force during the last step, published on
+/// `EventChannel<JointBroken>`. The broken joint stays in place without constraining the bodies,
+/// modifying or reinserting the component rebuilds it.
+#[derive(Clone, Debug)]
+pub struct JointBroken {
+    /// Entity holding the `Joint` component.
+    pub entity: Entity,
+    pub joint: Joint,
+}
+
+/// A `CharacterController` touched a shape while moving, published on
+/// `EventChannel<CharacterHit>`. Reported for every shape a move touches, including the ground.
+#[derive(Clone, Debug)]
+pub struct CharacterHit {
+    pub character: Entity,
+    pub other: Entity,
+    pub position: Point3<f32>,
+    /// Surface normal of the touched shape.
+    pub normal: Vector3<f32>,
+    /// Direction the character was moving in.
+    pub direction: Vector3<f32>,
+    /// Length of the move that hit the shape.
+    pub length: f32,
+}
+
+/// Contact reported by PhysX, the actors are not mapped to entities yet.
+pub(crate) struct RawContact {
+    actors: [usize; 2],
+    phase: ContactPhase,
+    points: Vec<ContactPoint>,
+}
+
+pub(crate) struct RawControllerHit {
+    pub(crate) actor: usize,
+    pub(crate) position: Point3<f32>,
+    pub(crate) normal: Vector3<f32>,
+    pub(crate) direction: Vector3<f32>,
+    pub(crate) length: f32,
+}
+
+pub(crate) struct RawTrigger {
+    trigger_actor: usize,
+    other_actor: usize,
+    enter: bool,
+}
+
+/// Buffers what the PhysX simulation callbacks report during `fetch_results`, until
+/// `PhysicsEventSystem` publishes it.
+#[derive(Default)]
+pub struct SimulationEvents {
+    pub(crate) contacts: Vec<RawContact>,
+    pub(crate) triggers: Vec<RawTrigger>,
+    /// `PxJoint` pointers of the joints that broke.
+    pub(crate) broken_joints: Vec<usize>,
+    /// Shapes touched by the controller moved last, filled during `PxController::move`.
+    pub(crate) controller_hits: Vec<RawControllerHit>,
+}
+
+impl SimulationEvents {
+    /// Installs the simulation event callback on `scene`, writing into `events`.
+    /// `events` has to outlive the scene.
+    pub(crate) unsafe fn register(scene: &mut Scene, events: &mut SimulationEvents) -> *mut PxSimulationEventCallback {
+        let user_data = events as *mut SimulationEvents as *mut c_void;
+        let info = SimulationEventCallbackInfo {
+            collision_callback: Some(on_contact),
+            collision_user_data: user_data,
+            trigger_callback: Some(on_trigger),
+            trigger_user_data: user_data,
+            constraint_break_callback: Some(on_constraint_break),
+            constraint_break_user_data: user_data,
+            wake_sleep_callback: None,
+            wake_sleep_user_data: null_mut(),
+            advance_callback: None,
+            advance_user_data: null_mut(),
+        };
+
+        let callback = create_simulation_event_callbacks(&info);
+        PxScene_setSimulationEventCallback_mut(scene.get_raw_mut(), callback);
+        callback
+    }
+
+    /// Creates the hit report every character controller is created with, writing into `events`.
+    /// `events` has to outlive the controllers.
+    pub(crate) unsafe fn register_controller_hits(events: &mut SimulationEvents) -> *mut PxUserControllerHitReport {
+        let user_data = events as *mut SimulationEvents as *mut c_void;
+        create_user_controller_hit_report(Some(on_controller_shape_hit), None, None, user_data)
+    }
+}
+
+/// Bit in `word2` of a shape's simulation filter data, set for colliders without `report_contacts`.
+pub(crate) const SKIP_CONTACT_DETAILS: u32 = 1;
+
+/// Filter shader that drops pairs whose `CollisionLayers` ignore each other, and asks PhysX to
+/// report touch events and contact points for every other pair. Persisting touches and contact
+/// points are left out when one of the shapes turned them off.
+pub(crate) unsafe extern "C" fn simulation_filter_shader(info: *mut FilterShaderCallbackInfo) -> u16 {
+    let info = &mut *info;
+
+    // Shapes without a layer, like the character controller capsules, collide with everything
+    let (data0, data1) = (&info.filterData0, &info.filterData1);
+    if data0.word0 != 0 && data1.word0 != 0 && (data0.word0 & data1.word1 == 0 || data1.word0 & data0.word1 == 0) {
+        return PxFilterFlag::eSUPPRESS as u16;
+    }
+
+    let trigger = PxFilterObjectFlag::eTRIGGER as u32;
+    if info.attributes0 & trigger != 0 || info.attributes1 & trigger != 0 {
+        (*info.pairFlags).mBits = PxPairFlag::eTRIGGER_DEFAULT as u16;
+        return PxFilterFlag::eDEFAULT as u16;
+    }
+
+    let mut flags = PxPairFlag::eCONTACT_DEFAULT | PxPairFlag::eNOTIFY_TOUCH_FOUND | PxPairFlag::eNOTIFY_TOUCH_LOST;
+    if (data0.word2 | data1.word2) & SKIP_CONTACT_DETAILS == 0 {
+        flags |= PxPairFlag::eNOTIFY_TOUCH_PERSISTS | PxPairFlag::eNOTIFY_CONTACT_POINTS;
+    }
+    (*info.pairFlags).mBits = flags as u16;
+    PxFilterFlag::eDEFAULT as u16
+}
+
+unsafe extern "C" fn on_contact(
+    user_data: *mut c_void,
+    header: *const PxContactPairHeader,
+    pairs: *const PxContactPair,
+    nb_pairs: u32,
+) {
+    let events = &mut *(user_data as *mut SimulationEvents);
+    let header = &*header;
+
+    // Released actors can't be mapped back to entities anymore
+    let removed = (PxContactPairHeaderFlag::eREMOVED_ACTOR_0 | PxContactPairHeaderFlag::eREMOVED_ACTOR_1) as u16;
+    if header.flags.mBits & removed != 0 {
+        return;
+    }
+    let actors = [header.actors[0] as usize, header.actors[1] as usize];
+
+    for pair in std::slice::from_raw_parts(pairs, nb_pairs as usize) {
+        let flags = pair.events.mBits;
+        let phase = if flags & PxPairFlag::eNOTIFY_TOUCH_FOUND as u16 != 0 {
+            ContactPhase::Begin
+        } else if flags & PxPairFlag::eNOTIFY_TOUCH_LOST as u16 != 0 {
+            ContactPhase::End
+        } else if flags & PxPairFlag::eNOTIFY_TOUCH_PERSISTS as u16 != 0 {
+            ContactPhase::Persist
+        } else {
+            continue;
+        };
+
+        let capacity = pair.contactCount as usize;
+        let mut buffer: Vec<PxContactPairPoint> = Vec::with_capacity(capacity);
+        let count = PxContactPair_extractContacts(pair, buffer.as_mut_ptr(), capacity as u32);
+        buffer.set_len(count as usize);
+
+        let points = buffer
+            .iter()
+            .map(|point| ContactPoint {
+                position: Point3::new(point.position.x, point.position.y, point.position.z),
+                normal: Vector3::new(point.normal.x, point.normal.y, point.normal.z),
+                impulse: Vector3::new(point.impulse.x, point.impulse.y, point.impulse.z),
+                separation: point.separation,
+            })
+            .collect();
+
+        events.contacts.push(RawContact { actors, phase, points });
+    }
+}
+
+unsafe extern "C" fn on_trigger(user_data: *mut c_void, pairs: *const PxTriggerPair, count: u32) {
+    let events = &mut *(user_data as *mut SimulationEvents);
+
+    let removed = (PxTriggerPairFlag::eREMOVED_SHAPE_TRIGGER | PxTriggerPairFlag::eREMOVED_SHAPE_OTHER) as u8;
+    for pair in std::slice::from_raw_parts(pairs, count as usize) {
+        if pair.flags.mBits & removed != 0 {
+            continue;
+        }
+
+        let enter = match pair.status {
+            status if status == PxPairFlag::eNOTIFY_TOUCH_FOUND => true,
+            status if status == PxPairFlag::eNOTIFY_TOUCH_LOST => false,
+            _ => continue,
+        };
+        events.triggers.push(RawTrigger {
+            trigger_actor: pair.triggerActor as usize,
+            other_actor: pair.otherActor as usize,
+            enter,
+        });
+    }
+}
+
+unsafe extern "C" fn on_constraint_break(user_data: *mut c_void, constraints: *const PxConstraintInfo, count: u32) {
+    let events = &mut *(user_data as *mut SimulationEvents);
+
+    // Every constraint we create is a joint, its external reference is the `PxJoint`
+    for info in std::slice::from_raw_parts(constraints, count as usize) {
+        if !info.externalReference.is_null() {
+            events.broken_joints.push(info.externalReference as usize);
+        }
+    }
+}
+
+unsafe extern "C" fn on_controller_shape_hit(user_data: *mut c_void, hit: *const PxControllerShapeHit) {
+    let events = &mut *(user_data as *mut SimulationEvents);
+    let hit = &*hit;
+
+    events.controller_hits.push(RawControllerHit {
+        actor: hit.actor as usize,
+        position: Point3::new(hit.worldPos.x as f32, hit.worldPos.y as f32, hit.worldPos.z as f32),
+        normal: Vector3::new(hit.worldNormal.x, hit.worldNormal.y, hit.worldNormal.z),
+        direction: Vector3::new(hit.dir.x, hit.dir.y, hit.dir.z),
+        length: hit.length,
+    });
+}
+
+/// Maps the contacts, trigger overlaps and joint breaks reported during the last step to entities
+/// and publishes them as `ContactEvent`s, `TriggerEvent`s and `JointBroken` events. Runs after the
+/// PhysX step.
+#[derive(SystemDesc)]
+pub struct PhysicsEventSystem;
+impl<'a> System<'a> for PhysicsEventSystem {
+    type SystemData = (
+        Write<'a, PhysXRef>,
+        Read<'a, PhysicsBodies>,
+        Read<'a, PhysicsJoints>,
+        ReadStorage<'a, Joint>,
+        Write<'a, EventChannel<ContactEvent>>,
+        Write<'a, EventChannel<TriggerEvent>>,
+        Write<'a, EventChannel<JointBroken>>,
+    );
+
+    fn run(
+        &mut self,
+        (physx, bodies, joints, joint_storage, mut contact_events, mut trigger_events, mut joint_events): Self::SystemData,
+    ) {
+        let mut physx_lock = physx.0.as_ref().unwrap().lock().unwrap();
+        let physx_ref = physx_lock.deref_mut();
+
+        for contact in physx_ref.events.contacts.drain(..) {
+            let [actor0, actor1] = contact.actors;
+            if let (Some(entity0), Some(entity1)) = (bodies.entity_by_ptr(actor0), bodies.entity_by_ptr(actor1)) {
+                contact_events.single_write(ContactEvent {
+                    entities: (entity0, entity1),
+                    phase: contact.phase,
+                    points: contact.points,
+                });
+            }
+        }
+
+        for raw in physx_ref.events.triggers.drain(..) {
+            let trigger = bodies.entity_by_ptr(raw.trigger_actor);
+            let other = bodies.entity_by_ptr(raw.other_actor);
+            if let (Some(trigger), Some(other)) = (trigger, other) {
+                trigger_events.single_write(if raw.enter {
+                    TriggerEvent::TriggerEnter { trigger, other }
+                } else {
+                    TriggerEvent::TriggerExit { trigger, other }
+                });
+            }
+        }
+
+        for joint_ptr in physx_ref.events.broken_joints.drain(..) {
+            if let Some(entity) = joints.entity_by_ptr(joint_ptr) {
+                if let Some(joint) = joint_storage.get(entity) {
+                    joint_events.single_write(JointBroken {
+                        entity,
+                        joint: joint.clone(),
+                    });
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,275 @@
+use amethyst::{
+    assets::{Asset, AssetStorage, Format, FormatValue, Handle, ProcessingState, Source},
+    core::{math::Matrix4, transform::Transform},
+    ecs::{Entities, Entity, Join, Read, ReadStorage, System, VecStorage, WriteStorage},
+    error::Error,
+};
+use gltf::{buffer, mesh::Mode, Gltf, Node};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::collider::{Collider, ColliderShape};
+
+/// PhysX geometry cooked from a glTF mesh.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GltfColliderKind {
+    #[serde(rename = "trimesh")]
+    TriangleMesh,
+    #[serde(rename = "convex")]
+    ConvexMesh,
+}
+
+impl GltfColliderKind {
+    /// Node name suffixes marking a node as a collider, like `Wall-col`.
+    const SUFFIXES: [(&'static str, GltfColliderKind); 2] = [
+        ("-convcol", GltfColliderKind::ConvexMesh),
+        ("-col", GltfColliderKind::TriangleMesh),
+    ];
+}
+
+/// Collider settings in the extras of a glTF node, which Blender exports from custom properties:
+/// `{ "collider": "convex", "layer": "props" }`.
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct ColliderExtras {
+    collider: Option<GltfColliderKind>,
+    layer: Option<String>,
+}
+
+/// Collider generated from a marked glTF node.
+#[derive(Clone, Debug)]
+pub struct GltfCollider {
+    pub name: String,
+    /// Mesh shape with the node's world transform baked into its vertices.
+    pub collider: Collider,
+}
+
+/// The colliders of a glTF file, loaded with `GltfColliderFormat`.
+///
+/// A node becomes a static collider when its name ends in `-col` for a triangle mesh or
+/// `-convcol` for a convex mesh, or when its extras set `"collider"` to `"trimesh"` or
+/// `"convex"`. The extras can also set the collision `"layer"`. Nodes of the default scene are
+/// used, and every triangle primitive of the node's mesh ends up in the shape.
+///
+/// Put a `Handle<GltfColliders>` on an entity to spawn the colliders at its `Transform`, next to
+/// the `GltfSceneAsset` rendering the same file.
+#[derive(Clone, Debug, Default)]
+pub struct GltfColliders {
+    pub colliders: Vec<GltfCollider>,
+}
+
+impl Asset for GltfColliders {
+    const NAME: &'static str = "physx::GltfColliders";
+    type Data = Self;
+    type HandleStorage = VecStorage<Handle<Self>>;
+}
+
+impl From<GltfColliders> for Result<ProcessingState<GltfColliders>, Error> {
+    fn from(colliders: GltfColliders) -> Self {
+        Ok(ProcessingState::Loaded(colliders))
+    }
+}
+
+/// Reads the colliders of `.gltf` and `.glb` files. Buffers can be embedded, data URIs or files
+/// next to the glTF file.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct GltfColliderFormat;
+
+impl Format<GltfColliders> for GltfColliderFormat {
+    fn name(&self) -> &'static str {
+        "GltfColliders"
+    }
+
+    fn import(
+        &self,
+        name: String,
+        source: Arc<dyn Source>,
+        _create_reload: Option<Box<dyn Format<GltfColliders>>>,
+    ) -> Result<FormatValue<GltfColliders>, Error> {
+        let gltf = Gltf::from_slice(&source.load(&name)?)?;
+        let buffers = load_buffers(&gltf, &name, &*source)?;
+
+        let mut colliders = Vec::new();
+        let scene = gltf.default_scene().or_else(|| gltf.scenes().next());
+        for node in scene.iter().flat_map(|scene| scene.nodes()) {
+            collect_colliders(&node, &Matrix4::identity(), &buffers, &mut colliders);
+        }
+
+        Ok(FormatValue::data(GltfColliders { colliders }))
+    }
+}
+
+fn load_buffers(gltf: &Gltf, name: &str, source: &dyn Source) -> Result<Vec<Vec<u8>>, Error> {
+    gltf.buffers()
+        .map(|buffer| match buffer.source() {
+            buffer::Source::Bin => gltf
+                .blob
+                .clone()
+                .ok_or_else(|| Error::from_string(format!("{} has no binary chunk", name))),
+            buffer::Source::Uri(uri) if uri.starts_with("data:") => {
+                let data = uri.splitn(2, ',').nth(1).unwrap_or("");
+                base64::decode(data).map_err(Error::new)
+            }
+            buffer::Source::Uri(uri) => {
+                let path = Path::new(name).parent().map_or_else(|| Path::new(uri).to_owned(), |dir| dir.join(uri));
+                source.load(&path.to_string_lossy())
+            }
+        })
+        .collect()
+}
+
+fn collect_colliders(node: &Node<'_>, parent: &Matrix4<f32>, buffers: &[Vec<u8>], colliders: &mut Vec<GltfCollider>) {
+    let world = parent * Matrix4::from(node.transform().matrix());
+
+    let name = node.name().unwrap_or("");
+    let extras = parse_extras(name, node.extras().as_ref().map(|extras| extras.get()));
+    let kind = collider_kind(name, &extras);
+
+    if let (Some(kind), Some(mesh)) = (kind, node.mesh()) {
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+        for primitive in mesh.primitives().filter(|primitive| primitive.mode() == Mode::Triangles) {
+            let reader = primitive.reader(|buffer| buffers.get(buffer.index()).map(Vec::as_slice));
+            let positions = match reader.read_positions() {
+                Some(positions) => positions,
+                None => continue,
+            };
+
+            let first = vertices.len() as u32;
+            vertices.extend(positions.map(|[x, y, z]| {
+                let world_position = world.transform_point(&[x, y, z].into());
+                [world_position.x, world_position.y, world_position.z]
+            }));
+            match reader.read_indices() {
+                Some(read) => indices.extend(read.into_u32().map(|index| first + index)),
+                None => indices.extend(first..vertices.len() as u32),
+            }
+        }
+
+        if vertices.is_empty() {
+            warn!("glTF node {:?} is marked as a collider but has no triangles", name);
+        } else {
+            let shape = match kind {
+                GltfColliderKind::TriangleMesh => ColliderShape::TriangleMesh { vertices, indices },
+                GltfColliderKind::ConvexMesh => ColliderShape::ConvexMesh { vertices },
+            };
+            let mut collider = Collider::new(shape);
+            if let Some(layer) = extras.layer {
+                collider = collider.with_layer(layer);
+            }
+            colliders.push(GltfCollider { name: name.to_owned(), collider });
+        }
+    }
+
+    for child in node.children() {
+        collect_colliders(&child, &world, buffers, colliders);
+    }
+}
+
+/// The collider settings in a node's extras JSON, invalid extras are ignored.
+fn parse_extras(name: &str, extras: Option<&str>) -> ColliderExtras {
+    extras
+        .and_then(|extras| match serde_json::from_str::<ColliderExtras>(extras) {
+            Ok(extras) => Some(extras),
+            Err(error) => {
+                warn!("Ignoring the extras of glTF node {:?}: {}", name, error);
+                None
+            }
+        })
+        .unwrap_or_default()
+}
+
+/// The kind of collider a node is marked as, the extras take precedence over the name suffix.
+fn collider_kind(name: &str, extras: &ColliderExtras) -> Option<GltfColliderKind> {
+    extras.collider.or_else(|| {
+        GltfColliderKind::SUFFIXES
+            .iter()
+            .find(|(suffix, _)| name.ends_with(suffix))
+            .map(|(_, kind)| *kind)
+    })
+}
+
+/// Spawns a static collider entity for every `GltfCollider` of a loaded `Handle<GltfColliders>`,
+/// and deletes them again when the handle is removed.
+#[derive(Default)]
+pub struct GltfColliderSystem {
+    spawned: HashMap<Entity, Vec<Entity>>,
+}
+
+impl<'a> System<'a> for GltfColliderSystem {
+    type SystemData = (
+        Entities<'a>,
+        Read<'a, AssetStorage<GltfColliders>>,
+        ReadStorage<'a, Handle<GltfColliders>>,
+        WriteStorage<'a, Collider>,
+        WriteStorage<'a, Transform>,
+    );
+
+    fn run(&mut self, (entities, storage, handles, mut colliders, mut transforms): Self::SystemData) {
+        for (entity, handle) in (&entities, &handles).join() {
+            if self.spawned.contains_key(&entity) {
+                continue;
+            }
+            let asset = match storage.get(handle) {
+                Some(asset) => asset,
+                None => continue,
+            };
+
+            let transform = transforms.get(entity).cloned().unwrap_or_default();
+            let mut spawned = Vec::with_capacity(asset.colliders.len());
+            for gltf_collider in &asset.colliders {
+                spawned.push(
+                    entities
+                        .build_entity()
+                        .with(gltf_collider.collider.clone(), &mut colliders)
+                        .with(transform.clone(), &mut transforms)
+                        .build(),
+                );
+            }
+            self.spawned.insert(entity, spawned);
+        }
+
+        self.spawned.retain(|owner, spawned| {
+            let alive = entities.is_alive(*owner) && handles.contains(*owner);
+            if !alive {
+                for entity in spawned.iter() {
+                    entities.delete(*entity).ok();
+                }
+            }
+            alive
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn name_suffix_marks_colliders() {
+        let extras = ColliderExtras::default();
+        assert_eq!(collider_kind("Wall-col", &extras), Some(GltfColliderKind::TriangleMesh));
+        assert_eq!(collider_kind("Crate-convcol", &extras), Some(GltfColliderKind::ConvexMesh));
+        assert_eq!(collider_kind("Wall", &extras), None);
+    }
+
+    #[test]
+    fn extras_override_name_suffix() {
+        let extras = parse_extras("Rock-col", Some(r#"{ "collider": "convex", "layer": "props" }"#));
+        assert_eq!(collider_kind("Rock-col", &extras), Some(GltfColliderKind::ConvexMesh));
+        assert_eq!(extras.layer.as_deref(), Some("props"));
+    }
+
+    #[test]
+    fn unrelated_or_invalid_extras_are_ignored() {
+        let extras = parse_extras("Lamp", Some(r#"{ "light": 2.5 }"#));
+        assert_eq!(collider_kind("Lamp", &extras), None);
+
+        let extras = parse_extras("Lamp-col", Some(r#"{ "collider": "sphere" }"#));
+        assert_eq!(collider_kind("Lamp-col", &extras), Some(GltfColliderKind::TriangleMesh));
+        assert_eq!(extras.layer, None);
+    }
+}
@@ -0,0 +1,192 @@
+use amethyst::{
+    core::{math::Point3, transform::Transform},
+    ecs::{Entity, Read, ReadExpect, ReadStorage, System, Write},
+    input::{BindingTypes, InputHandler},
+    renderer::{camera::{ActiveCamera, Camera}, debug_drawing::DebugLines, palette::Srgba},
+    window::ScreenDimensions,
+};
+use physx_sys::{
+    phys_PxD6JointCreate, PxActor, PxActor_release_mut, PxArticulationBase_wakeUp_mut,
+    PxArticulationLink, PxArticulationLink_getArticulation, PxBase, PxBase_getConcreteType,
+    PxConcreteType, PxD6Axis, PxD6Drive, PxD6Joint, PxD6JointDrive_new_1, PxD6Joint_setDrive_mut,
+    PxD6Joint_setMotion_mut, PxD6Motion, PxJoint, PxJoint_release_mut,
+    PxPhysics_createRigidDynamic_mut, PxQuat, PxRigidActor, PxRigidActor_getGlobalPose,
+    PxRigidBody, PxRigidBodyFlag, PxRigidBody_setRigidBodyFlag_mut, PxRigidDynamic,
+    PxRigidDynamic_setKinematicTarget_mut, PxRigidDynamic_wakeUp_mut, PxScene_addActor_mut,
+    PxTransform, PxVec3,
+};
+use std::ops::DerefMut;
+use std::ptr::null;
+
+use crate::bodies::PhysicsBodies;
+use crate::picking::{cursor_ray, PickedEntity};
+use crate::resources::{PhysXRef, PhysxResources};
+use crate::rigid_body::BodyType;
+use crate::transform_conv::px_to_isometry;
+
+/// An active grab, the PhysX objects are stored as addresses so the system stays `Send`.
+struct Grab {
+    entity: Entity,
+    /// `PhysicsBodies` generation of the grabbed actor, the joint is attached to that actor only.
+    generation: u64,
+    anchor: usize,
+    joint: usize,
+    /// Attachment point in the grabbed actor's local space.
+    local_point: Point3<f32>,
+    /// Distance from the camera at which the anchor follows the cursor.
+    distance: f32,
+}
+
+/// Wakes up a dynamic body, which is either a `PxRigidDynamic` or the link of an articulation that
+/// has to be woken up as a whole.
+unsafe fn wake_up(actor: usize) {
+    if PxBase_getConcreteType(actor as *const PxBase) == PxConcreteType::eARTICULATION_LINK as u16 {
+        let articulation = PxArticulationLink_getArticulation(actor as *const PxArticulationLink);
+        PxArticulationBase_wakeUp_mut(articulation);
+    } else {
+        PxRigidDynamic_wakeUp_mut(actor as *mut PxRigidDynamic);
+    }
+}
+
+/// Lets the user drag the picked dynamic body around while `action` is held, including the links
+/// of ragdolls and other articulations.
+///
+/// A shapeless kinematic anchor follows the cursor at the distance the body was grabbed at, and a
+/// D6 joint with a spring drive pulls the body towards it, like the PhysX samples do.
+/// Runs after `PickingSystem` and needs PhysX extensions to be loaded.
+pub struct GrabSystem<B: BindingTypes> {
+    pub action: B::Action,
+    pub stiffness: f32,
+    pub damping: f32,
+    grab: Option<Grab>,
+}
+
+impl<B: BindingTypes> GrabSystem<B> {
+    pub fn new(action: B::Action, stiffness: f32, damping: f32) -> Self {
+        GrabSystem {
+            action,
+            stiffness,
+            damping,
+            grab: None,
+        }
+    }
+
+    unsafe fn start_grab(&self, physx: &mut PhysxResources, entity: Entity, generation: u64, actor: usize, point: Point3<f32>, distance: f32) -> Grab {
+        let PhysxResources { physics, scene, .. } = physx;
+        let physics = physics.as_mut().unwrap();
+
+        let actor_pose = px_to_isometry(&PxRigidActor_getGlobalPose(actor as *const PxRigidActor));
+        let local_point = actor_pose.inverse_transform_point(&point);
+
+        let anchor_pose = PxTransform {
+            q: PxQuat { x: 0.0, y: 0.0, z: 0.0, w: 1.0 },
+            p: PxVec3 { x: point.x, y: point.y, z: point.z },
+        };
+        let anchor = PxPhysics_createRigidDynamic_mut(physics.get_raw_mut(), &anchor_pose);
+        PxRigidBody_setRigidBodyFlag_mut(anchor as *mut PxRigidBody, PxRigidBodyFlag::eKINEMATIC, true);
+        PxScene_addActor_mut(scene.get_raw_mut(), anchor as *mut PxActor, null());
+
+        let identity = PxTransform {
+            q: PxQuat { x: 0.0, y: 0.0, z: 0.0, w: 1.0 },
+            p: PxVec3 { x: 0.0, y: 0.0, z: 0.0 },
+        };
+        let local_frame = PxTransform {
+            q: identity.q,
+            p: PxVec3 { x: local_point.x, y: local_point.y, z: local_point.z },
+        };
+        let joint = phys_PxD6JointCreate(
+            physics.get_raw_mut(),
+            anchor as *mut PxRigidActor,
+            &identity,
+            actor as *mut PxRigidActor,
+            &local_frame,
+        );
+        for axis in &[PxD6Axis::eX, PxD6Axis::eY, PxD6Axis::eZ, PxD6Axis::eTWIST, PxD6Axis::eSWING1, PxD6Axis::eSWING2] {
+            PxD6Joint_setMotion_mut(joint, *axis, PxD6Motion::eFREE);
+        }
+        let drive = PxD6JointDrive_new_1(self.stiffness, self.damping, std::f32::MAX, true);
+        PxD6Joint_setDrive_mut(joint, PxD6Drive::eX, &drive);
+        PxD6Joint_setDrive_mut(joint, PxD6Drive::eY, &drive);
+        PxD6Joint_setDrive_mut(joint, PxD6Drive::eZ, &drive);
+        wake_up(actor);
+
+        Grab {
+            entity,
+            generation,
+            anchor: anchor as usize,
+            joint: joint as usize,
+            local_point,
+            distance,
+        }
+    }
+
+    unsafe fn release_grab(grab: Grab) {
+        PxJoint_release_mut(grab.joint as *mut PxD6Joint as *mut PxJoint);
+        PxActor_release_mut(grab.anchor as *mut PxActor);
+    }
+}
+
+impl<'a, B: BindingTypes> System<'a> for GrabSystem<B> {
+    type SystemData = (
+        Read<'a, InputHandler<B>>,
+        ReadExpect<'a, ScreenDimensions>,
+        Read<'a, ActiveCamera>,
+        ReadStorage<'a, Camera>,
+        ReadStorage<'a, Transform>,
+        Read<'a, PickedEntity>,
+        Read<'a, PhysicsBodies>,
+        Write<'a, PhysXRef>,
+        Write<'a, DebugLines>,
+    );
+
+    fn run(&mut self, (input, screen_dimensions, active_camera, cameras, transforms, picked, bodies, physx, mut debug_lines): Self::SystemData) {
+        let mut physx_lock = physx.0.as_ref().unwrap().lock().unwrap();
+        let physx_ref = physx_lock.deref_mut();
+
+        let held = input.action_is_down(&self.action).unwrap_or(false);
+        // A rebuilt actor is a different PhysX object than the one the joint holds on to
+        let grabbed_alive = self
+            .grab
+            .as_ref()
+            .map_or(false, |grab| bodies.generation(grab.entity) == Some(grab.generation));
+        if !held || !grabbed_alive {
+            if let Some(grab) = self.grab.take() {
+                unsafe { Self::release_grab(grab) };
+            }
+        }
+        if !held {
+            return;
+        }
+
+        let ray = match cursor_ray(&input, &screen_dimensions, &active_camera, &cameras, &transforms) {
+            Some(ray) => ray,
+            None => return,
+        };
+
+        if self.grab.is_none() {
+            if let Some(hit) = &picked.hit {
+                if let Some((handle, BodyType::Dynamic)) = bodies.get(hit.entity) {
+                    let distance = (hit.position - ray.origin).norm();
+                    let generation = bodies.generation(hit.entity).unwrap();
+                    self.grab = Some(unsafe { self.start_grab(physx_ref, hit.entity, generation, handle.0, hit.position, distance) });
+                }
+            }
+        }
+
+        if let Some(grab) = &self.grab {
+            let target = ray.origin + ray.direction.normalize() * grab.distance;
+            let target_pose = PxTransform {
+                q: PxQuat { x: 0.0, y: 0.0, z: 0.0, w: 1.0 },
+                p: PxVec3 { x: target.x, y: target.y, z: target.z },
+            };
+
+            let attach_point = unsafe {
+                PxRigidDynamic_setKinematicTarget_mut(grab.anchor as *mut PxRigidDynamic, &target_pose);
+
+                let actor = bodies.actor(grab.entity).unwrap();
+                px_to_isometry(&PxRigidActor_getGlobalPose(actor.0 as *const PxRigidActor)) * grab.local_point
+            };
+            debug_lines.draw_line(target, attach_point, Srgba::new(1.0, 0.8, 0.0, 1.0));
+        }
+    }
+}
@@ -0,0 +1,86 @@
+use amethyst::{
+    core::{math::Isometry3, transform::Transform},
+    derive::SystemDesc,
+    ecs::{Component, DenseVecStorage, Join, Read, ReadStorage, System, SystemData, WriteStorage},
+};
+
+use crate::physics_time::PhysicsTime;
+
+/// Actor poses of the last two physics steps, kept for every synced `RigidBody`.
+#[derive(Clone, Debug)]
+pub struct PhysicsPose {
+    pub previous: Isometry3<f32>,
+    pub current: Isometry3<f32>,
+}
+
+impl PhysicsPose {
+    pub fn new(pose: Isometry3<f32>) -> Self {
+        PhysicsPose {
+            previous: pose,
+            current: pose,
+        }
+    }
+
+    /// Blends between the previous and current step, `alpha` of 0 is the previous pose.
+    pub fn interpolate(&self, alpha: f32) -> Isometry3<f32> {
+        let translation = self.previous.translation.vector.lerp(&self.current.translation.vector, alpha);
+        let rotation = self.previous.rotation
+            .try_slerp(&self.current.rotation, alpha, 1.0e-6)
+            .unwrap_or(self.current.rotation);
+
+        Isometry3::from_parts(translation.into(), rotation)
+    }
+}
+
+impl Component for PhysicsPose {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Writes the blend of the last two physics poses to `Transform`, using the leftover alpha of
+/// `PhysicsTime`. Runs after `RigidBodySyncSystem` and before the transform system.
+#[derive(SystemDesc)]
+pub struct PhysicsInterpolationSystem;
+impl<'a> System<'a> for PhysicsInterpolationSystem {
+    type SystemData = (
+        Read<'a, PhysicsTime>,
+        ReadStorage<'a, PhysicsPose>,
+        WriteStorage<'a, Transform>,
+    );
+
+    fn run(&mut self, (physics_time, poses, mut transforms): Self::SystemData) {
+        let alpha = physics_time.alpha();
+
+        for (pose, transform) in (&poses, &mut transforms).join() {
+            let isometry = pose.interpolate(alpha);
+            transform.set_translation(isometry.translation.vector);
+            transform.set_rotation(isometry.rotation);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use amethyst::core::math::{Translation3, UnitQuaternion, Vector3};
+
+    fn pose(x: f32, angle: f32) -> Isometry3<f32> {
+        Isometry3::from_parts(Translation3::new(x, 0.0, 0.0), UnitQuaternion::from_axis_angle(&Vector3::y_axis(), angle))
+    }
+
+    #[test]
+    fn blends_translation_and_rotation() {
+        let physics_pose = PhysicsPose { previous: pose(0.0, 0.0), current: pose(2.0, 1.0) };
+
+        let blended = physics_pose.interpolate(0.25);
+        assert!((blended.translation.vector.x - 0.5).abs() < 1.0e-6);
+        assert!((blended.rotation.angle() - 0.25).abs() < 1.0e-6);
+    }
+
+    #[test]
+    fn ends_at_previous_and_current() {
+        let physics_pose = PhysicsPose { previous: pose(1.0, 0.5), current: pose(3.0, 1.5) };
+
+        assert!((physics_pose.interpolate(0.0).translation.vector.x - 1.0).abs() < 1.0e-6);
+        assert!((physics_pose.interpolate(1.0).rotation.angle() - 1.5).abs() < 1.0e-6);
+    }
+}
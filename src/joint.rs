@@ -0,0 +1,434 @@
+use amethyst::{
+    core::math::{Isometry3, Vector3},
+    derive::SystemDesc,
+    ecs::{
+        storage::ComponentEvent, world::Index, Component, DenseVecStorage, Entities, Entity,
+        FlaggedStorage, Join, Read, ReadStorage, ReaderId, System, SystemData, Write,
+    },
+};
+use physx::prelude::*;
+use physx_sys::{
+    phys_PxD6JointCreate, phys_PxDistanceJointCreate, phys_PxFixedJointCreate,
+    phys_PxPrismaticJointCreate, phys_PxRevoluteJointCreate, phys_PxSphericalJointCreate,
+    PxConstraintFlag, PxConstraint_getFlags, PxConstraint_getForce_mut, PxD6Axis, PxD6Drive, PxD6JointDrive_new_1, PxD6Joint_setDrivePosition_mut,
+    PxD6Joint_setDrive_mut, PxD6Joint_setMotion_mut, PxD6Joint_setSwingLimit_mut,
+    PxD6Joint_setTwistLimit_mut, PxD6Joint_setDistanceLimit_mut, PxD6Motion,
+    PxDistanceJointFlag, PxDistanceJoint_setDamping_mut, PxDistanceJoint_setDistanceJointFlag_mut,
+    PxDistanceJoint_setMaxDistance_mut, PxDistanceJoint_setMinDistance_mut,
+    PxDistanceJoint_setStiffness_mut, PxJoint, PxJointAngularLimitPair_new, PxJointLimitCone_new,
+    PxJointLinearLimitPair_new, PxJointLinearLimit_new, PxJoint_getConstraint, PxJoint_release_mut,
+    PxJoint_setBreakForce_mut, PxVec3, PxPhysics, PxPhysics_getTolerancesScale, PxPrismaticJointFlag,
+    PxPrismaticJoint_setLimit_mut, PxPrismaticJoint_setPrismaticJointFlag_mut, PxRevoluteJointFlag,
+    PxRevoluteJoint_setDriveForceLimit_mut, PxRevoluteJoint_setDriveVelocity_mut,
+    PxRevoluteJoint_setLimit_mut, PxRevoluteJoint_setRevoluteJointFlag_mut, PxRigidActor,
+    PxSphericalJointFlag, PxSphericalJoint_setLimitCone_mut,
+    PxSphericalJoint_setSphericalJointFlag_mut,
+};
+use std::collections::{HashMap, HashSet};
+use std::ops::DerefMut;
+use std::ptr::null_mut;
+
+use crate::bodies::PhysicsBodies;
+use crate::resources::PhysXRef;
+use crate::transform_conv::isometry_to_px;
+
+/// Lower and upper bound of a limited degree of freedom, in radians or meters.
+#[derive(Clone, Copy, Debug)]
+pub struct LimitPair {
+    pub lower: f32,
+    pub upper: f32,
+}
+
+/// Cone limit around the joint's X axis, half angles in radians.
+#[derive(Clone, Copy, Debug)]
+pub struct LimitCone {
+    pub y_angle: f32,
+    pub z_angle: f32,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct RevoluteDrive {
+    /// Target angular velocity in radians per second.
+    pub velocity: f32,
+    pub force_limit: f32,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Spring {
+    pub stiffness: f32,
+    pub damping: f32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum D6Axis {
+    X,
+    Y,
+    Z,
+    Twist,
+    Swing1,
+    Swing2,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum D6Motion {
+    Locked,
+    Limited,
+    Free,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum D6DriveAxis {
+    X,
+    Y,
+    Z,
+    Swing,
+    Twist,
+    Slerp,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct D6Drive {
+    pub stiffness: f32,
+    pub damping: f32,
+    pub force_limit: f32,
+    /// Drive acceleration instead of force, ignoring the mass of the bodies.
+    pub acceleration: bool,
+}
+
+/// Type specific settings of a `Joint`.
+#[derive(Clone, Debug)]
+pub enum JointKind {
+    Fixed,
+    /// Rotates around the X axis of the joint frames.
+    Revolute {
+        limit: Option<LimitPair>,
+        drive: Option<RevoluteDrive>,
+    },
+    Spherical {
+        limit: Option<LimitCone>,
+    },
+    /// Slides along the X axis of the joint frames.
+    Prismatic {
+        limit: Option<LimitPair>,
+    },
+    /// Keeps the joint frame origins within a distance range.
+    Distance {
+        min_distance: Option<f32>,
+        max_distance: Option<f32>,
+        spring: Option<Spring>,
+    },
+    /// Every axis is locked unless it is listed in `motions`.
+    D6 {
+        motions: Vec<(D6Axis, D6Motion)>,
+        linear_limit: Option<f32>,
+        twist_limit: Option<LimitPair>,
+        swing_limit: Option<LimitCone>,
+        drives: Vec<(D6DriveAxis, D6Drive)>,
+        /// Pose of frame 1 relative to frame 0 the drives pull towards.
+        drive_target: Isometry3<f32>,
+    },
+}
+
+/// Connects the actors of two entities with a PhysX joint.
+///
+/// The component can live on one of the connected bodies or on its own entity, which allows a body
+/// to have several joints. Without `body1` the first body is attached to a fixed point in the world.
+/// `JointSystem` creates the joint once both actors exist and rebuilds it when the component is
+/// modified or one of the actors is recreated.
+#[derive(Clone, Debug)]
+pub struct Joint {
+    pub kind: JointKind,
+    pub body0: Entity,
+    pub body1: Option<Entity>,
+    /// Joint frame relative to the actor of `body0`.
+    pub local_frame0: Isometry3<f32>,
+    /// Joint frame relative to the actor of `body1`, or to the world without one.
+    pub local_frame1: Isometry3<f32>,
+    /// Force and torque at which the joint breaks, unbreakable when `None`.
+    pub break_force: Option<(f32, f32)>,
+}
+
+impl Joint {
+    pub fn new(kind: JointKind, body0: Entity, body1: Option<Entity>) -> Self {
+        Joint {
+            kind,
+            body0,
+            body1,
+            local_frame0: Isometry3::identity(),
+            local_frame1: Isometry3::identity(),
+            break_force: None,
+        }
+    }
+
+    pub fn with_frames(mut self, local_frame0: Isometry3<f32>, local_frame1: Isometry3<f32>) -> Self {
+        self.local_frame0 = local_frame0;
+        self.local_frame1 = local_frame1;
+        self
+    }
+
+    pub fn with_break_force(mut self, force: f32, torque: f32) -> Self {
+        self.break_force = Some((force, torque));
+        self
+    }
+
+    unsafe fn create(&self, physics: *mut PxPhysics, actor0: *mut PxRigidActor, actor1: *mut PxRigidActor) -> *mut PxJoint {
+        let frame0 = isometry_to_px(&self.local_frame0);
+        let frame1 = isometry_to_px(&self.local_frame1);
+        let scale = PxPhysics_getTolerancesScale(physics);
+
+        let joint = match &self.kind {
+            JointKind::Fixed => phys_PxFixedJointCreate(physics, actor0, &frame0, actor1, &frame1) as *mut PxJoint,
+            JointKind::Revolute { limit, drive } => {
+                let joint = phys_PxRevoluteJointCreate(physics, actor0, &frame0, actor1, &frame1);
+                if let Some(limit) = limit {
+                    PxRevoluteJoint_setLimit_mut(joint, &PxJointAngularLimitPair_new(limit.lower, limit.upper, -1.0));
+                    PxRevoluteJoint_setRevoluteJointFlag_mut(joint, PxRevoluteJointFlag::eLIMIT_ENABLED, true);
+                }
+                if let Some(drive) = drive {
+                    PxRevoluteJoint_setDriveVelocity_mut(joint, drive.velocity, true);
+                    PxRevoluteJoint_setDriveForceLimit_mut(joint, drive.force_limit);
+                    PxRevoluteJoint_setRevoluteJointFlag_mut(joint, PxRevoluteJointFlag::eDRIVE_ENABLED, true);
+                }
+                joint as *mut PxJoint
+            }
+            JointKind::Spherical { limit } => {
+                let joint = phys_PxSphericalJointCreate(physics, actor0, &frame0, actor1, &frame1);
+                if let Some(limit) = limit {
+                    PxSphericalJoint_setLimitCone_mut(joint, &PxJointLimitCone_new(limit.y_angle, limit.z_angle, -1.0));
+                    PxSphericalJoint_setSphericalJointFlag_mut(joint, PxSphericalJointFlag::eLIMIT_ENABLED, true);
+                }
+                joint as *mut PxJoint
+            }
+            JointKind::Prismatic { limit } => {
+                let joint = phys_PxPrismaticJointCreate(physics, actor0, &frame0, actor1, &frame1);
+                if let Some(limit) = limit {
+                    PxPrismaticJoint_setLimit_mut(joint, &PxJointLinearLimitPair_new(scale, limit.lower, limit.upper, -1.0));
+                    PxPrismaticJoint_setPrismaticJointFlag_mut(joint, PxPrismaticJointFlag::eLIMIT_ENABLED, true);
+                }
+                joint as *mut PxJoint
+            }
+            JointKind::Distance { min_distance, max_distance, spring } => {
+                let joint = phys_PxDistanceJointCreate(physics, actor0, &frame0, actor1, &frame1);
+                if let Some(min_distance) = min_distance {
+                    PxDistanceJoint_setMinDistance_mut(joint, *min_distance);
+                    PxDistanceJoint_setDistanceJointFlag_mut(joint, PxDistanceJointFlag::eMIN_DISTANCE_ENABLED, true);
+                }
+                if let Some(max_distance) = max_distance {
+                    PxDistanceJoint_setMaxDistance_mut(joint, *max_distance);
+                    PxDistanceJoint_setDistanceJointFlag_mut(joint, PxDistanceJointFlag::eMAX_DISTANCE_ENABLED, true);
+                }
+                if let Some(spring) = spring {
+                    PxDistanceJoint_setStiffness_mut(joint, spring.stiffness);
+                    PxDistanceJoint_setDamping_mut(joint, spring.damping);
+                    PxDistanceJoint_setDistanceJointFlag_mut(joint, PxDistanceJointFlag::eSPRING_ENABLED, true);
+                }
+                joint as *mut PxJoint
+            }
+            JointKind::D6 { motions, linear_limit, twist_limit, swing_limit, drives, drive_target } => {
+                let joint = phys_PxD6JointCreate(physics, actor0, &frame0, actor1, &frame1);
+                for (axis, motion) in motions {
+                    let axis = match axis {
+                        D6Axis::X => PxD6Axis::eX,
+                        D6Axis::Y => PxD6Axis::eY,
+                        D6Axis::Z => PxD6Axis::eZ,
+                        D6Axis::Twist => PxD6Axis::eTWIST,
+                        D6Axis::Swing1 => PxD6Axis::eSWING1,
+                        D6Axis::Swing2 => PxD6Axis::eSWING2,
+                    };
+                    let motion = match motion {
+                        D6Motion::Locked => PxD6Motion::eLOCKED,
+                        D6Motion::Limited => PxD6Motion::eLIMITED,
+                        D6Motion::Free => PxD6Motion::eFREE,
+                    };
+                    PxD6Joint_setMotion_mut(joint, axis, motion);
+                }
+                if let Some(extent) = linear_limit {
+                    PxD6Joint_setDistanceLimit_mut(joint, &PxJointLinearLimit_new(scale, *extent, -1.0));
+                }
+                if let Some(limit) = twist_limit {
+                    PxD6Joint_setTwistLimit_mut(joint, &PxJointAngularLimitPair_new(limit.lower, limit.upper, -1.0));
+                }
+                if let Some(limit) = swing_limit {
+                    PxD6Joint_setSwingLimit_mut(joint, &PxJointLimitCone_new(limit.y_angle, limit.z_angle, -1.0));
+                }
+                for (axis, drive) in drives {
+                    let axis = match axis {
+                        D6DriveAxis::X => PxD6Drive::eX,
+                        D6DriveAxis::Y => PxD6Drive::eY,
+                        D6DriveAxis::Z => PxD6Drive::eZ,
+                        D6DriveAxis::Swing => PxD6Drive::eSWING,
+                        D6DriveAxis::Twist => PxD6Drive::eTWIST,
+                        D6DriveAxis::Slerp => PxD6Drive::eSLERP,
+                    };
+                    let px_drive = PxD6JointDrive_new_1(drive.stiffness, drive.damping, drive.force_limit, drive.acceleration);
+                    PxD6Joint_setDrive_mut(joint, axis, &px_drive);
+                }
+                if !drives.is_empty() {
+                    PxD6Joint_setDrivePosition_mut(joint, &isometry_to_px(drive_target), true);
+                }
+                joint as *mut PxJoint
+            }
+        };
+
+        if let Some((force, torque)) = self.break_force {
+            PxJoint_setBreakForce_mut(joint, force, torque);
+        }
+        joint
+    }
+}
+
+impl Component for Joint {
+    type Storage = FlaggedStorage<Self, DenseVecStorage<Self>>;
+}
+
+struct JointEntry {
+    entity: Entity,
+    joint: usize,
+    /// `PhysicsBodies` generations of the connected actors, 0 for the world.
+    generation0: u64,
+    generation1: u64,
+}
+
+/// Map between `Joint` entities and the PhysX joints created for them.
+#[derive(Default)]
+pub struct PhysicsJoints {
+    by_index: HashMap<Index, JointEntry>,
+    by_joint: HashMap<usize, Entity>,
+}
+
+impl PhysicsJoints {
+    pub fn joint(&self, entity: Entity) -> Option<*mut PxJoint> {
+        self.by_index
+            .get(&entity.id())
+            .filter(|entry| entry.entity == entity)
+            .map(|entry| entry.joint as *mut PxJoint)
+    }
+
+    /// Looks up the entity owning a raw `PxJoint` pointer, as handed out by PhysX callbacks.
+    pub fn entity_by_ptr(&self, joint: usize) -> Option<Entity> {
+        self.by_joint.get(&joint).copied()
+    }
+
+    /// Linear force and torque the joint applied during the last step.
+    pub fn force(&self, entity: Entity) -> Option<(Vector3<f32>, Vector3<f32>)> {
+        self.joint(entity).map(|joint| unsafe {
+            let mut linear = PxVec3 { x: 0.0, y: 0.0, z: 0.0 };
+            let mut angular = PxVec3 { x: 0.0, y: 0.0, z: 0.0 };
+            PxConstraint_getForce_mut(PxJoint_getConstraint(joint), &mut linear, &mut angular);
+            (
+                Vector3::new(linear.x, linear.y, linear.z),
+                Vector3::new(angular.x, angular.y, angular.z),
+            )
+        })
+    }
+
+    /// Whether the joint exceeded its break force and no longer constrains the bodies.
+    pub fn is_broken(&self, entity: Entity) -> bool {
+        self.joint(entity).map_or(false, |joint| unsafe {
+            let flags = PxConstraint_getFlags(PxJoint_getConstraint(joint));
+            flags.mBits & PxConstraintFlag::eBROKEN as u16 != 0
+        })
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Entity, *mut PxJoint)> + '_ {
+        self.by_index.values().map(|entry| (entry.entity, entry.joint as *mut PxJoint))
+    }
+
+    fn insert(&mut self, entry: JointEntry) {
+        self.by_joint.insert(entry.joint, entry.entity);
+        self.by_index.insert(entry.entity.id(), entry);
+    }
+
+    /// Releases the PhysX joint of the entity at `index`, if there is one.
+    unsafe fn release_index(&mut self, index: Index) {
+        if let Some(entry) = self.by_index.remove(&index) {
+            self.by_joint.remove(&entry.joint);
+            PxJoint_release_mut(entry.joint as *mut PxJoint);
+        }
+    }
+}
+
+/// Creates, rebuilds and releases PhysX joints for `Joint` components.
+/// Runs after `RigidBodySyncSystem`, so actors created this frame can be connected right away.
+#[derive(SystemDesc)]
+pub struct JointSystem {
+    #[system_desc(flagged_storage_reader(Joint))]
+    joint_reader: ReaderId<ComponentEvent>,
+}
+
+impl JointSystem {
+    pub fn new(joint_reader: ReaderId<ComponentEvent>) -> Self {
+        JointSystem { joint_reader }
+    }
+}
+
+impl<'a> System<'a> for JointSystem {
+    type SystemData = (
+        Entities<'a>,
+        Write<'a, PhysXRef>,
+        Read<'a, PhysicsBodies>,
+        Write<'a, PhysicsJoints>,
+        ReadStorage<'a, Joint>,
+    );
+
+    fn run(&mut self, (entities, physx, bodies, mut joints, joint_storage): Self::SystemData) {
+        let mut physx_lock = physx.0.as_ref().unwrap().lock().unwrap();
+        let physx_ref = physx_lock.deref_mut();
+        let physics = physx_ref.physics.as_mut().unwrap().get_raw_mut();
+
+        let mut dirty = HashSet::new();
+        for event in joint_storage.channel().read(&mut self.joint_reader) {
+            match event {
+                ComponentEvent::Modified(index) | ComponentEvent::Removed(index) => {
+                    dirty.insert(*index);
+                }
+                ComponentEvent::Inserted(_) => {}
+            }
+        }
+
+        for (entity, joint) in (&entities, &joint_storage).join() {
+            let body = |entity: Entity| bodies.actor(entity).map(|handle| (handle.0, bodies.generation(entity).unwrap()));
+            let body0 = body(joint.body0);
+            let body1 = match joint.body1 {
+                Some(body1) => body(body1),
+                None => Some((0, 0)),
+            };
+
+            // Rebuild when the component changed or one of the actors was released or rebuilt, which
+            // can reuse the address of the old actor
+            let stale = match joints.by_index.get(&entity.id()) {
+                Some(entry) => {
+                    dirty.contains(&entity.id())
+                        || entry.entity != entity
+                        || body0.map(|(_, generation)| generation) != Some(entry.generation0)
+                        || body1.map(|(_, generation)| generation) != Some(entry.generation1)
+                }
+                None => false,
+            };
+            if stale {
+                unsafe { joints.release_index(entity.id()) };
+            }
+
+            if joints.by_index.contains_key(&entity.id()) {
+                continue;
+            }
+            if let (Some((actor0, generation0)), Some((actor1, generation1))) = (body0, body1) {
+                let actor1_ptr = if actor1 == 0 { null_mut() } else { actor1 as *mut PxRigidActor };
+                let px_joint = unsafe { joint.create(physics, actor0 as *mut PxRigidActor, actor1_ptr) };
+                joints.insert(JointEntry {
+                    entity,
+                    joint: px_joint as usize,
+                    generation0,
+                    generation1,
+                });
+            }
+        }
+
+        // Joints whose component is gone, including deleted entities
+        for index in dirty {
+            if !joint_storage.mask().contains(index) {
+                unsafe { joints.release_index(index) };
+            }
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use log::warn;
+use physx_sys::PxFilterData;
+use serde::{Deserialize, Serialize};
+
+/// Named collision layers and the pairs of them that don't collide, usually loaded from
+/// `config/collision_layers.ron`:
+///
+/// ```ron
+/// (
+///     layers: ["default", "character", "debris"],
+///     ignored_pairs: [("character", "debris"), ("debris", "debris")],
+/// )
+/// ```
+///
+/// Every `Collider` is on one layer, and two shapes only collide if neither of their layers
+/// ignores the other. `QueryFilter::for_layer` makes scene queries follow the same rules.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CollisionLayers {
+    /// Up to 32 layers, shapes on unknown layers are put on the first one.
+    pub layers: Vec<String>,
+    pub ignored_pairs: Vec<(String, String)>,
+}
+
+impl Default for CollisionLayers {
+    fn default() -> Self {
+        CollisionLayers {
+            layers: vec![String::from("default")],
+            ignored_pairs: Vec::new(),
+        }
+    }
+}
+
+impl CollisionLayers {
+    /// Bit of the layer called `name`.
+    pub fn layer(&self, name: &str) -> Option<u32> {
+        self.layers
+            .iter()
+            .take(32)
+            .position(|layer| layer == name)
+            .map(|index| 1 << index)
+    }
+
+    /// Bits of all the named layers, unknown names are skipped.
+    pub fn mask(&self, names: &[&str]) -> u32 {
+        names.iter().filter_map(|name| self.layer(name)).fold(0, |mask, bit| mask | bit)
+    }
+
+    /// Bits of the layers that collide with the layer called `name`.
+    pub fn collision_mask(&self, name: &str) -> u32 {
+        self.ignored_pairs
+            .iter()
+            .filter_map(|(a, b)| match (a == name, b == name) {
+                (true, _) => self.layer(b),
+                (_, true) => self.layer(a),
+                _ => None,
+            })
+            .fold(!0, |mask, ignored| mask & !ignored)
+    }
+
+    /// Filter data read by the filter shader and scene queries: the shape's layer bit in `word0`
+    /// and the layers it collides with in `word1`.
+    pub(crate) fn filter_data(&self, name: &str) -> PxFilterData {
+        let name = if self.layer(name).is_some() {
+            name
+        } else {
+            let fallback = self.layers.first().map_or("", String::as_str);
+            warn!("Unknown collision layer {:?}, using {:?} instead", name, fallback);
+            fallback
+        };
+
+        PxFilterData {
+            word0: self.layer(name).unwrap_or(1),
+            word1: self.collision_mask(name),
+            word2: 0,
+            word3: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layers() -> CollisionLayers {
+        CollisionLayers {
+            layers: vec![String::from("default"), String::from("character"), String::from("debris")],
+            ignored_pairs: vec![
+                (String::from("character"), String::from("debris")),
+                (String::from("debris"), String::from("debris")),
+            ],
+        }
+    }
+
+    #[test]
+    fn layer_bits_follow_their_order() {
+        let layers = layers();
+        assert_eq!(layers.layer("default"), Some(1));
+        assert_eq!(layers.layer("debris"), Some(4));
+        assert_eq!(layers.layer("unknown"), None);
+        assert_eq!(layers.mask(&["character", "debris", "unknown"]), 6);
+    }
+
+    #[test]
+    fn collision_mask_leaves_out_ignored_pairs() {
+        let layers = layers();
+        assert_eq!(layers.collision_mask("default"), !0);
+        assert_eq!(layers.collision_mask("character"), !4);
+        assert_eq!(layers.collision_mask("debris"), !6);
+    }
+
+    #[test]
+    fn filter_data_falls_back_to_first_layer() {
+        let layers = layers();
+        let debris = layers.filter_data("debris");
+        assert_eq!((debris.word0, debris.word1), (4, !6));
+
+        let unknown = layers.filter_data("unknown");
+        assert_eq!((unknown.word0, unknown.word1), (1, !0));
+    }
+}
@@ -0,0 +1,62 @@
+//! PhysX integration for amethyst, built on top of physx-rs.
+//!
+//! Add the `PhysicsBundle` to your game data and attach `RigidBody` and `Collider` components to
+//! entities with a `Transform` to have them simulated.
+
+pub mod articulation;
+pub mod bodies;
+pub mod bundle;
+pub mod character;
+pub mod collider;
+pub mod color_conv;
+pub mod config;
+pub mod cooking;
+pub mod debug_render;
+pub mod events;
+pub mod gltf_collider;
+pub mod grab;
+pub mod interpolation;
+pub mod joint;
+pub mod layers;
+pub mod material;
+pub mod physics_time;
+pub mod physx_system;
+pub mod picking;
+pub mod prefab;
+pub mod queries;
+pub mod ragdoll;
+pub mod resources;
+pub mod rigid_body;
+pub mod transform_conv;
+
+pub use articulation::{
+    Articulation, ArticulationAxis, ArticulationDrive, ArticulationJoint, ArticulationJointKind, ArticulationLink,
+    ArticulationSystem, PhysicsArticulations,
+};
+pub use bodies::{PhysicsBodies, PhysicsCleanupSystem};
+pub use bundle::PhysicsBundle;
+pub use character::{
+    CharacterController, CharacterControllerSystem, CharacterInputSystem, PhysicsControllers, PlayerCharacter,
+};
+pub use collider::{Collider, ColliderShape};
+pub use config::{BroadphaseType, MaterialConfig, PhysicsConfig, SceneFlags, SolverType};
+pub use debug_render::PhysXDebugRenderSystem;
+pub use events::{
+    CharacterHit, ContactEvent, ContactPhase, ContactPoint, JointBroken, PhysicsEventSystem, TriggerEvent,
+};
+pub use gltf_collider::{
+    GltfCollider, GltfColliderFormat, GltfColliderKind, GltfColliderSystem, GltfColliders,
+};
+pub use grab::GrabSystem;
+pub use interpolation::{PhysicsInterpolationSystem, PhysicsPose};
+pub use joint::{Joint, JointKind, JointSystem, PhysicsJoints};
+pub use layers::CollisionLayers;
+pub use material::{CombineMode, PhysicsMaterial, PhysicsMaterialSystem};
+pub use physics_time::PhysicsTime;
+pub use physx_system::PhysXSystem;
+pub use picking::{PickedEntity, PickingSystem};
+pub use prefab::{PhysicsMaterialPrefab, PhysicsPrefab};
+pub use queries::{PhysicsQueries, QueryFilter, QueryHit, QueryShape};
+pub use ragdoll::{Ragdoll, RagdollBone, RagdollConfig, RagdollMode, RagdollSystem};
+pub use resources::{PhysXRef, PhysxResources, PX_PHYSICS_VERSION};
+pub use rigid_body::{BodyType, RigidBody, RigidBodySyncSystem};
@@ -0,0 +1,224 @@
+use amethyst::{
+    assets::{Asset, AssetStorage, Handle, ProcessingState, WeakHandle},
+    derive::SystemDesc,
+    ecs::{Read, System, SystemData, VecStorage, Write},
+    error::Error,
+};
+use physx::prelude::*;
+use physx_sys::{
+    PxCombineMode, PxMaterial, PxMaterial_setDynamicFriction_mut,
+    PxMaterial_setFrictionCombineMode_mut, PxMaterial_setRestitutionCombineMode_mut,
+    PxMaterial_release_mut, PxMaterial_setRestitution_mut, PxMaterial_setStaticFriction_mut,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::collider::Collider;
+use crate::resources::PhysXRef;
+
+/// How the values of two touching materials are combined, PhysX uses the mode with the highest
+/// priority of the two, in the order `Average < Min < Multiply < Max`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CombineMode {
+    Average,
+    Min,
+    Multiply,
+    Max,
+}
+
+impl Default for CombineMode {
+    fn default() -> Self {
+        CombineMode::Average
+    }
+}
+
+impl CombineMode {
+    fn to_px(self) -> PxCombineMode::Enum {
+        match self {
+            CombineMode::Average => PxCombineMode::eAVERAGE,
+            CombineMode::Min => PxCombineMode::eMIN,
+            CombineMode::Multiply => PxCombineMode::eMULTIPLY,
+            CombineMode::Max => PxCombineMode::eMAX,
+        }
+    }
+}
+
+/// Surface properties of a `Collider`, loaded as an asset from RON:
+///
+/// ```ron
+/// (
+///     static_friction: 0.8,
+///     dynamic_friction: 0.6,
+///     restitution: 0.1,
+///     friction_combine: Max,
+/// )
+/// ```
+///
+/// Changes to a loaded material, for example through hot reloading, are applied to every shape
+/// using it.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PhysicsMaterial {
+    pub static_friction: f32,
+    pub dynamic_friction: f32,
+    pub restitution: f32,
+    pub friction_combine: CombineMode,
+    pub restitution_combine: CombineMode,
+}
+
+impl Default for PhysicsMaterial {
+    fn default() -> Self {
+        PhysicsMaterial {
+            static_friction: 0.5,
+            dynamic_friction: 0.5,
+            restitution: 0.6,
+            friction_combine: CombineMode::Average,
+            restitution_combine: CombineMode::Average,
+        }
+    }
+}
+
+impl PhysicsMaterial {
+    unsafe fn create(&self, physics: &mut Physics) -> *mut PxMaterial {
+        let material = physics.create_material(self.static_friction, self.dynamic_friction, self.restitution);
+        self.apply_combine_modes(material);
+        material
+    }
+
+    unsafe fn apply(&self, material: *mut PxMaterial) {
+        PxMaterial_setStaticFriction_mut(material, self.static_friction);
+        PxMaterial_setDynamicFriction_mut(material, self.dynamic_friction);
+        PxMaterial_setRestitution_mut(material, self.restitution);
+        self.apply_combine_modes(material);
+    }
+
+    unsafe fn apply_combine_modes(&self, material: *mut PxMaterial) {
+        PxMaterial_setFrictionCombineMode_mut(material, self.friction_combine.to_px());
+        PxMaterial_setRestitutionCombineMode_mut(material, self.restitution_combine.to_px());
+    }
+}
+
+impl Asset for PhysicsMaterial {
+    const NAME: &'static str = "physx::PhysicsMaterial";
+    type Data = Self;
+    type HandleStorage = VecStorage<Handle<Self>>;
+}
+
+impl From<PhysicsMaterial> for Result<ProcessingState<PhysicsMaterial>, Error> {
+    fn from(material: PhysicsMaterial) -> Self {
+        Ok(ProcessingState::Loaded(material))
+    }
+}
+
+struct MaterialEntry {
+    /// Weak, so the asset unloads once no collider uses it anymore.
+    handle: WeakHandle<PhysicsMaterial>,
+    material: usize,
+    /// The values the PhysX material currently has, to notice reloads.
+    applied: PhysicsMaterial,
+}
+
+/// The PhysX materials created for `PhysicsMaterial` assets, one per asset, owned by
+/// `PhysxResources`.
+///
+/// Shapes hold their own reference to their material, so releasing a material whose asset was
+/// dropped only destroys it once the last shape using it is gone.
+#[derive(Default)]
+pub(crate) struct PhysicsMaterials {
+    by_id: HashMap<u32, MaterialEntry>,
+}
+
+impl PhysicsMaterials {
+    /// Material for the shapes of `collider`, or `default` if it has none.
+    /// Returns `None` while the collider's material asset is still loading.
+    pub(crate) fn resolve(
+        &mut self,
+        collider: Option<&Collider>,
+        storage: &AssetStorage<PhysicsMaterial>,
+        physics: &mut Physics,
+        default: *mut PxMaterial,
+    ) -> Option<*mut PxMaterial> {
+        let handle = match collider.and_then(|collider| collider.material.as_ref()) {
+            Some(handle) => handle,
+            None => return Some(default),
+        };
+        match self.by_id.get(&handle.id()) {
+            Some(entry) if !entry.handle.is_dead() => return Some(entry.material as *mut PxMaterial),
+            // The id was reused after the old asset was dropped
+            Some(_) => self.release(handle.id()),
+            None => {}
+        }
+
+        let asset = storage.get(handle)?;
+        let material = unsafe { asset.create(physics) };
+        self.by_id.insert(
+            handle.id(),
+            MaterialEntry {
+                handle: handle.downgrade(),
+                material: material as usize,
+                applied: asset.clone(),
+            },
+        );
+        Some(material)
+    }
+
+    fn release(&mut self, id: u32) {
+        if let Some(entry) = self.by_id.remove(&id) {
+            unsafe { PxMaterial_release_mut(entry.material as *mut PxMaterial) };
+        }
+    }
+
+    /// Releases every material, called before the `PxPhysics` that created them is released.
+    pub(crate) fn release_all(&mut self) {
+        for (_, entry) in self.by_id.drain() {
+            unsafe { PxMaterial_release_mut(entry.material as *mut PxMaterial) };
+        }
+    }
+}
+
+/// Whether the material assets of all colliders have finished loading.
+pub(crate) fn materials_loaded<'a>(
+    colliders: impl IntoIterator<Item = &'a Collider>,
+    storage: &AssetStorage<PhysicsMaterial>,
+) -> bool {
+    colliders
+        .into_iter()
+        .filter_map(|collider| collider.material.as_ref())
+        .all(|handle| storage.get(handle).is_some())
+}
+
+/// Applies reloaded `PhysicsMaterial` assets to their PhysX materials, which updates every shape
+/// using them, and releases the materials of dropped assets.
+#[derive(SystemDesc)]
+pub struct PhysicsMaterialSystem;
+impl<'a> System<'a> for PhysicsMaterialSystem {
+    type SystemData = (
+        Write<'a, PhysXRef>,
+        Read<'a, AssetStorage<PhysicsMaterial>>,
+    );
+
+    fn run(&mut self, (physx, storage): Self::SystemData) {
+        let mut physx_lock = physx.0.as_ref().unwrap().lock().unwrap();
+        let materials = &mut physx_lock.materials;
+
+        let mut dropped = Vec::new();
+        for (id, entry) in materials.by_id.iter_mut() {
+            let handle = match entry.handle.upgrade() {
+                Some(handle) => handle,
+                None => {
+                    dropped.push(*id);
+                    continue;
+                }
+            };
+            if let Some(asset) = storage.get(&handle) {
+                if *asset != entry.applied {
+                    unsafe { asset.apply(entry.material as *mut PxMaterial) };
+                    entry.applied = asset.clone();
+                }
+            }
+        }
+        for id in dropped {
+            materials.release(id);
+        }
+    }
+}
@@ -0,0 +1,105 @@
+use log::warn;
+
+/// Fixed timestep bookkeeping for `PhysXSystem`.
+///
+/// Frame time is accumulated and the scene is stepped in `fixed_timestep` increments, at most
+/// `max_substeps` times per frame. Time that could not be simulated because of the cap is dropped,
+/// so a long frame can't make the next frames fall further behind.
+#[derive(Clone, Debug)]
+pub struct PhysicsTime {
+    pub fixed_timestep: f32,
+    pub max_substeps: u32,
+    accumulator: f32,
+    alpha: f32,
+    steps_last_frame: u32,
+}
+
+impl Default for PhysicsTime {
+    fn default() -> Self {
+        PhysicsTime::new(1.0 / 60.0, 4)
+    }
+}
+
+impl PhysicsTime {
+    /// A `fixed_timestep` that isn't positive would never advance the simulation, so it's replaced by
+    /// the default of 1/60th of a second.
+    pub fn new(fixed_timestep: f32, max_substeps: u32) -> Self {
+        let fixed_timestep = if fixed_timestep > 0.0 {
+            fixed_timestep
+        } else {
+            warn!("Invalid fixed timestep {}, using 1/60 s instead", fixed_timestep);
+            1.0 / 60.0
+        };
+
+        PhysicsTime {
+            fixed_timestep,
+            max_substeps,
+            accumulator: 0.0,
+            alpha: 0.0,
+            steps_last_frame: 0,
+        }
+    }
+
+    /// Adds the frame time and returns how many fixed steps should be simulated this frame.
+    pub fn advance(&mut self, delta_seconds: f32) -> u32 {
+        self.accumulator += delta_seconds;
+
+        let mut steps = 0;
+        while self.accumulator >= self.fixed_timestep && steps < self.max_substeps {
+            self.accumulator -= self.fixed_timestep;
+            steps += 1;
+        }
+        if self.accumulator >= self.fixed_timestep {
+            self.accumulator %= self.fixed_timestep;
+        }
+
+        self.alpha = self.accumulator / self.fixed_timestep;
+        self.steps_last_frame = steps;
+        steps
+    }
+
+    /// Fraction of a fixed step that is left in the accumulator, in the range `[0, 1)`.
+    pub fn alpha(&self) -> f32 {
+        self.alpha
+    }
+
+    pub fn steps_last_frame(&self) -> u32 {
+        self.steps_last_frame
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn steps_whole_timesteps() {
+        let mut time = PhysicsTime::new(0.25, 4);
+        assert_eq!(time.advance(0.5), 2);
+        assert_eq!(time.advance(0.125), 0);
+        assert_eq!(time.advance(0.125), 1);
+        assert_eq!(time.steps_last_frame(), 1);
+    }
+
+    #[test]
+    fn caps_steps_and_drops_remainder() {
+        let mut time = PhysicsTime::new(0.25, 2);
+        assert_eq!(time.advance(1.125), 2);
+        assert_eq!(time.alpha(), 0.5);
+        // Only the half step that was left over is carried into the next frame
+        assert_eq!(time.advance(0.125), 1);
+    }
+
+    #[test]
+    fn alpha_is_leftover_fraction() {
+        let mut time = PhysicsTime::new(0.25, 4);
+        time.advance(0.3125);
+        assert_eq!(time.alpha(), 0.25);
+    }
+
+    #[test]
+    fn rejects_invalid_timestep() {
+        assert_eq!(PhysicsTime::new(0.0, 4).fixed_timestep, 1.0 / 60.0);
+        assert_eq!(PhysicsTime::new(f32::NAN, 4).fixed_timestep, 1.0 / 60.0);
+    }
+}
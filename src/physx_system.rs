@@ -0,0 +1,84 @@
+use amethyst::{
+    core::{transform::Transform, Time},
+    derive::SystemDesc,
+    ecs::{Entities, Join, Read, ReadStorage, System, SystemData, World, Write, WriteStorage},
+};
+use physx_sys::{
+    PxRigidActor, PxRigidActor_getGlobalPose, PxRigidDynamic, PxRigidDynamic_setKinematicTarget_mut,
+};
+use std::ops::DerefMut;
+
+use crate::bodies::PhysicsBodies;
+use crate::interpolation::PhysicsPose;
+use crate::physics_time::PhysicsTime;
+use crate::resources::PhysXRef;
+use crate::rigid_body::{BodyType, RigidBody};
+use crate::transform_conv::{isometry_to_px, px_to_isometry};
+
+/// Steps the PhysX scene at the fixed rate of `PhysicsTime` and blocks until the results are available.
+#[derive(SystemDesc)]
+pub struct PhysXSystem;
+impl<'a> System<'a> for PhysXSystem {
+    type SystemData = (Read<'a, Time>,
+        Write<'a, PhysicsTime>,
+        Write<'a, PhysXRef>,
+        Entities<'a>,
+        Read<'a, PhysicsBodies>,
+        WriteStorage<'a, PhysicsPose>,
+        ReadStorage<'a, RigidBody>,
+        ReadStorage<'a, Transform>,
+    );
+
+    fn run(&mut self, (time, mut physics_time, physx, entities, bodies, mut poses, rigid_bodies, transforms): Self::SystemData) {
+        let mut physx_lock = physx.0.as_ref().unwrap().lock().unwrap();
+        let physx_ref = physx_lock.deref_mut();
+
+        let steps = physics_time.advance(time.delta_seconds());
+
+        // PhysX clears a kinematic target after each step, so every substep moves the kinematic
+        // bodies part of the way from their current pose to their `Transform`
+        let mut kinematic_moves = Vec::new();
+        if steps > 0 {
+            for (entity, rigid_body, transform) in (&entities, &rigid_bodies, &transforms).join() {
+                if rigid_body.body_type != BodyType::Kinematic {
+                    continue;
+                }
+                if let Some((handle, BodyType::Kinematic)) = bodies.get(entity) {
+                    let start = unsafe { px_to_isometry(&PxRigidActor_getGlobalPose(handle.0 as *const PxRigidActor)) };
+                    let path = PhysicsPose { previous: start, current: *transform.isometry() };
+                    kinematic_moves.push((handle.0, path));
+                }
+            }
+        }
+        for step in 0..steps {
+            let progress = (step + 1) as f32 / steps as f32;
+            for (actor, path) in &kinematic_moves {
+                unsafe {
+                    PxRigidDynamic_setKinematicTarget_mut(*actor as *mut PxRigidDynamic, &isometry_to_px(&path.interpolate(progress)));
+                }
+            }
+            if step + 1 == steps {
+                // Interpolation blends between the last two steps, so remember the pose before the last one
+                for (entity, pose) in (&entities, &mut poses).join() {
+                    if let Some(handle) = bodies.actor(entity) {
+                        let actor_pose = unsafe { PxRigidActor_getGlobalPose(handle.0 as *const PxRigidActor) };
+                        pose.previous = px_to_isometry(&actor_pose);
+                    }
+                }
+            }
+
+            physx_ref.scene.simulate(physics_time.fixed_timestep);
+            physx_ref.scene
+                .fetch_results(true)
+                .expect("error occured during simulation");
+        }
+    }
+
+    fn dispose(self, world: &mut World)
+    {
+        if world.try_fetch::<PhysXRef>().is_some() {
+            world.remove::<PhysXRef>();
+        }
+    }
+
+}
@@ -0,0 +1,112 @@
+use amethyst::{
+    core::{
+        geometry::Ray,
+        math::{Point2, Unit, Vector2},
+        transform::Transform,
+    },
+    ecs::{Entities, Entity, Join, Read, ReadExpect, ReadStorage, System, Write},
+    input::{BindingTypes, InputHandler},
+    renderer::{camera::{ActiveCamera, Camera}, debug_drawing::DebugLines, palette::Srgba},
+    window::ScreenDimensions,
+};
+use amethyst_imgui::imgui;
+use amethyst_imgui::imgui::im_str;
+use std::marker::PhantomData;
+
+use crate::queries::{PhysicsQueries, QueryFilter, QueryHit};
+
+/// The body currently under the mouse cursor, updated by `PickingSystem`.
+#[derive(Clone, Debug, Default)]
+pub struct PickedEntity {
+    pub hit: Option<QueryHit>,
+}
+
+impl PickedEntity {
+    pub fn entity(&self) -> Option<Entity> {
+        self.hit.as_ref().map(|hit| hit.entity)
+    }
+}
+
+/// World space ray from the active camera, or the first camera if none is active, through the
+/// mouse cursor.
+pub fn cursor_ray<B: BindingTypes>(
+    input: &InputHandler<B>,
+    screen_dimensions: &ScreenDimensions,
+    active_camera: &ActiveCamera,
+    cameras: &ReadStorage<'_, Camera>,
+    transforms: &ReadStorage<'_, Transform>,
+) -> Option<Ray<f32>> {
+    let (camera, camera_transform) = active_camera
+        .entity
+        .and_then(|entity| Some((cameras.get(entity)?, transforms.get(entity)?)))
+        .or_else(|| (cameras, transforms).join().next())?;
+    let (x, y) = input.mouse_position()?;
+
+    Some(camera.screen_ray(
+        Point2::new(x, y),
+        Vector2::new(screen_dimensions.width(), screen_dimensions.height()),
+        camera_transform,
+    ))
+}
+
+/// Casts a ray from the active camera through the mouse cursor and stores the first body it hits
+/// in `PickedEntity`. The picked body is outlined with debug lines.
+///
+/// Has to run after the transform system, so the camera's global matrix is up to date.
+pub struct PickingSystem<B: BindingTypes> {
+    pub max_distance: f32,
+    pub filter: QueryFilter,
+    _marker: PhantomData<B>,
+}
+
+impl<B: BindingTypes> Default for PickingSystem<B> {
+    fn default() -> Self {
+        PickingSystem {
+            max_distance: 1000.0,
+            filter: QueryFilter::default(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<'a, B: BindingTypes> System<'a> for PickingSystem<B> {
+    type SystemData = (
+        Entities<'a>,
+        Read<'a, InputHandler<B>>,
+        ReadExpect<'a, ScreenDimensions>,
+        Read<'a, ActiveCamera>,
+        ReadStorage<'a, Camera>,
+        ReadStorage<'a, Transform>,
+        PhysicsQueries<'a>,
+        Write<'a, PickedEntity>,
+        Write<'a, DebugLines>,
+    );
+
+    fn run(&mut self, (entities, input, screen_dimensions, active_camera, cameras, transforms, queries, mut picked, mut debug_lines): Self::SystemData) {
+        picked.hit = cursor_ray(&input, &screen_dimensions, &active_camera, &cameras, &transforms)
+            .and_then(|ray| queries.raycast(ray.origin, Unit::new_normalize(ray.direction), self.max_distance, self.filter));
+
+        if let Some(hit) = &picked.hit {
+            let highlight = Srgba::new(1.0, 0.8, 0.0, 1.0);
+            if let Some((min, max)) = queries.world_bounds(hit.entity) {
+                debug_lines.draw_box(min, max, highlight);
+            }
+            debug_lines.draw_direction(hit.position, hit.normal * 0.5, highlight);
+        }
+
+        amethyst_imgui::with(|ui| {
+            imgui::Window::new(im_str!("Picking"))
+                .size([250f32, 100f32], imgui::Condition::Once)
+                .build(&ui, || {
+                match &picked.hit {
+                    Some(hit) if entities.is_alive(hit.entity) => {
+                        ui.text(format!("Entity: {}", hit.entity.id()));
+                        ui.text(format!("Distance: {:.2}", hit.distance));
+                        ui.text(format!("Position: [{:.2}, {:.2}, {:.2}]", hit.position.x, hit.position.y, hit.position.z));
+                    }
+                    _ => ui.text("Nothing under the cursor"),
+                }
+            });
+        });
+    }
+}
@@ -0,0 +1,124 @@
+use amethyst::{
+    assets::{AssetStorage, Handle, Loader, PrefabData, ProgressCounter, RonFormat},
+    ecs::{Entity, Read, ReadExpect, WriteStorage},
+    error::Error,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::collider::Collider;
+use crate::material::PhysicsMaterial;
+use crate::rigid_body::RigidBody;
+
+/// `PhysicsMaterial` of a prefab, either shared through an asset file or defined in place.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum PhysicsMaterialPrefab {
+    /// Path of a RON material asset, loaded once for every prefab using it.
+    File(String),
+    Inline(PhysicsMaterial),
+    /// The loaded material, which `File` and `Inline` turn into when the prefab loads.
+    #[serde(skip)]
+    Handle(Handle<PhysicsMaterial>),
+}
+
+impl<'a> PrefabData<'a> for PhysicsMaterialPrefab {
+    type SystemData = (ReadExpect<'a, Loader>, Read<'a, AssetStorage<PhysicsMaterial>>);
+    type Result = Handle<PhysicsMaterial>;
+
+    fn add_to_entity(
+        &self,
+        _: Entity,
+        _: &mut Self::SystemData,
+        _: &[Entity],
+        _: &[Entity],
+    ) -> Result<Handle<PhysicsMaterial>, Error> {
+        match self {
+            PhysicsMaterialPrefab::Handle(handle) => Ok(handle.clone()),
+            _ => Err(Error::from_string("PhysicsMaterialPrefab is used before its material was loaded")),
+        }
+    }
+
+    fn load_sub_assets(
+        &mut self,
+        progress: &mut ProgressCounter,
+        (loader, storage): &mut Self::SystemData,
+    ) -> Result<bool, Error> {
+        let handle = match self {
+            PhysicsMaterialPrefab::File(path) => loader.load(path.as_str(), RonFormat, &mut *progress, &**storage),
+            PhysicsMaterialPrefab::Inline(material) => loader.load_from_data(material.clone(), &mut *progress, &**storage),
+            PhysicsMaterialPrefab::Handle(_) => return Ok(false),
+        };
+        *self = PhysicsMaterialPrefab::Handle(handle);
+        Ok(true)
+    }
+}
+
+/// Physics components of a prefab entity, the actor is created by `RigidBodySyncSystem` once the
+/// prefab is instantiated and its material has loaded:
+///
+/// ```ron
+/// #![enable(implicit_some)]
+/// Prefab(
+///     entities: [
+///         PrefabEntity(
+///             data: (
+///                 transform: (translation: (0.0, 0.5, 0.0)),
+///                 physics: (
+///                     rigid_body: (density: 10.0),
+///                     collider: (shape: Box(half_extents: (0.5, 0.5, 0.5))),
+///                     material: File("materials/rubber.ron"),
+///                 ),
+///             ),
+///         ),
+///     ],
+/// )
+/// ```
+///
+/// Without a `rigid_body` the collider gets a static actor, like in code.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PhysicsPrefab {
+    pub rigid_body: Option<RigidBody>,
+    pub collider: Option<Collider>,
+    /// Material of `collider`, the default material from `PhysicsConfig` without one.
+    pub material: Option<PhysicsMaterialPrefab>,
+}
+
+impl<'a> PrefabData<'a> for PhysicsPrefab {
+    type SystemData = (
+        WriteStorage<'a, RigidBody>,
+        WriteStorage<'a, Collider>,
+        <PhysicsMaterialPrefab as PrefabData<'a>>::SystemData,
+    );
+    type Result = ();
+
+    fn add_to_entity(
+        &self,
+        entity: Entity,
+        (rigid_bodies, colliders, material_data): &mut Self::SystemData,
+        entities: &[Entity],
+        children: &[Entity],
+    ) -> Result<(), Error> {
+        if let Some(rigid_body) = &self.rigid_body {
+            rigid_bodies.insert(entity, rigid_body.clone())?;
+        }
+        if let Some(collider) = &self.collider {
+            let mut collider = collider.clone();
+            if let Some(material) = &self.material {
+                collider.material = Some(material.add_to_entity(entity, material_data, entities, children)?);
+            }
+            colliders.insert(entity, collider)?;
+        }
+        Ok(())
+    }
+
+    fn load_sub_assets(
+        &mut self,
+        progress: &mut ProgressCounter,
+        (_, _, material_data): &mut Self::SystemData,
+    ) -> Result<bool, Error> {
+        match &mut self.material {
+            Some(material) => material.load_sub_assets(progress, material_data),
+            None => Ok(false),
+        }
+    }
+}
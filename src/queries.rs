@@ -0,0 +1,283 @@
+use amethyst::{
+    core::math::{Isometry3, Point3, Unit, Vector3},
+    ecs::{Entity, Read, SystemData},
+};
+use physx::prelude::*;
+use physx_sys::{
+    PxActor, PxActor_getWorldBounds, PxBoxGeometry_new_1, PxCapsuleGeometry_new_1, PxFilterData, PxGeometry, PxHitFlag,
+    PxHitFlags, PxOverlapHit, PxQueryFilterData, PxQueryFlag, PxQueryFlags, PxRaycastHit,
+    PxSceneQueryExt_overlapMultiple, PxSceneQueryExt_raycastMultiple, PxSceneQueryExt_sweepMultiple,
+    PxSphereGeometry_new_1, PxSweepHit, PxVec3,
+};
+use std::ptr::{null, null_mut};
+
+use crate::bodies::PhysicsBodies;
+use crate::layers::CollisionLayers;
+use crate::resources::PhysXRef;
+use crate::transform_conv::isometry_to_px;
+
+/// Which shapes a scene query considers.
+///
+/// A shape is hit when the bit of its `Collider::layer` is in `mask`, build masks from layer names
+/// with `CollisionLayers::mask`.
+#[derive(Clone, Copy, Debug)]
+pub struct QueryFilter {
+    pub mask: u32,
+    pub static_bodies: bool,
+    pub dynamic_bodies: bool,
+}
+
+impl Default for QueryFilter {
+    fn default() -> Self {
+        QueryFilter {
+            mask: !0,
+            static_bodies: true,
+            dynamic_bodies: true,
+        }
+    }
+}
+
+impl QueryFilter {
+    pub fn with_mask(mask: u32) -> Self {
+        QueryFilter {
+            mask,
+            ..Default::default()
+        }
+    }
+
+    /// Hits the shapes that collide with shapes on the layer called `layer`.
+    pub fn for_layer(layers: &CollisionLayers, layer: &str) -> Self {
+        QueryFilter::with_mask(layers.collision_mask(layer))
+    }
+
+    /// Filter data that reports every hit as a touch, so hits on unmapped actors don't hide the ones
+    /// behind them.
+    fn to_px_touching(&self) -> PxQueryFilterData {
+        let mut data = self.to_px();
+        data.flags.mBits |= PxQueryFlag::eNO_BLOCK as u16;
+        data
+    }
+
+    fn to_px(&self) -> PxQueryFilterData {
+        let mut flags = 0;
+        if self.static_bodies {
+            flags |= PxQueryFlag::eSTATIC as u16;
+        }
+        if self.dynamic_bodies {
+            flags |= PxQueryFlag::eDYNAMIC as u16;
+        }
+
+        PxQueryFilterData {
+            data: PxFilterData { word0: self.mask, word1: 0, word2: 0, word3: 0 },
+            flags: PxQueryFlags { mBits: flags },
+        }
+    }
+}
+
+/// Shapes that can be swept through or overlapped with the scene.
+#[derive(Clone, Copy, Debug)]
+pub enum QueryShape {
+    Sphere { radius: f32 },
+    Box { half_extents: [f32; 3] },
+    /// Aligned to the local X axis, like `ColliderShape::Capsule`.
+    Capsule { radius: f32, half_height: f32 },
+}
+
+impl QueryShape {
+    unsafe fn with_geometry<R>(&self, f: impl FnOnce(*const PxGeometry) -> R) -> R {
+        match *self {
+            QueryShape::Sphere { radius } => {
+                let geometry = PxSphereGeometry_new_1(radius);
+                f(&geometry as *const _ as *const PxGeometry)
+            }
+            QueryShape::Box { half_extents: [x, y, z] } => {
+                let geometry = PxBoxGeometry_new_1(x, y, z);
+                f(&geometry as *const _ as *const PxGeometry)
+            }
+            QueryShape::Capsule { radius, half_height } => {
+                let geometry = PxCapsuleGeometry_new_1(radius, half_height);
+                f(&geometry as *const _ as *const PxGeometry)
+            }
+        }
+    }
+}
+
+/// Closest hit of a raycast or sweep.
+#[derive(Clone, Debug)]
+pub struct QueryHit {
+    pub entity: Entity,
+    pub position: Point3<f32>,
+    pub normal: Vector3<f32>,
+    pub distance: f32,
+}
+
+/// Scene queries against the PhysX scene, with hit actors mapped back to their entities.
+///
+/// Hits on actors that don't belong to an entity are ignored.
+#[derive(SystemData)]
+pub struct PhysicsQueries<'a> {
+    physx: Read<'a, PhysXRef>,
+    bodies: Read<'a, PhysicsBodies>,
+}
+
+impl<'a> PhysicsQueries<'a> {
+    /// Maximum number of entities returned by a single overlap test.
+    pub const MAX_OVERLAPS: usize = 256;
+    /// Maximum number of shapes a raycast or sweep looks at when finding the closest entity.
+    pub const MAX_HITS: usize = 64;
+
+    pub fn raycast(
+        &self,
+        origin: Point3<f32>,
+        direction: Unit<Vector3<f32>>,
+        max_distance: f32,
+        filter: QueryFilter,
+    ) -> Option<QueryHit> {
+        let physx_lock = self.physx.0.as_ref().unwrap().lock().unwrap();
+
+        let hits = unsafe {
+            let mut hits: Vec<PxRaycastHit> = Vec::with_capacity(Self::MAX_HITS);
+            let mut blocking_hit = false;
+            let count = PxSceneQueryExt_raycastMultiple(
+                physx_lock.scene.get_raw(),
+                &to_px_vec3(&origin.coords),
+                &to_px_vec3(&direction),
+                max_distance,
+                PxHitFlags { mBits: PxHitFlag::eDEFAULT as u16 },
+                hits.as_mut_ptr(),
+                Self::MAX_HITS as u32,
+                &mut blocking_hit,
+                &filter.to_px_touching(),
+                null_mut(),
+                null(),
+            );
+            hits.set_len(if count < 0 { Self::MAX_HITS } else { count as usize });
+            hits
+        };
+
+        self.closest(hits.iter().map(|hit| (hit.actor as usize, hit.position, hit.normal, hit.distance)))
+    }
+
+    /// Moves `shape` from `pose` along `direction` and returns the first shape it touches.
+    pub fn sweep(
+        &self,
+        shape: QueryShape,
+        pose: &Isometry3<f32>,
+        direction: Unit<Vector3<f32>>,
+        max_distance: f32,
+        filter: QueryFilter,
+    ) -> Option<QueryHit> {
+        let physx_lock = self.physx.0.as_ref().unwrap().lock().unwrap();
+
+        let hits = unsafe {
+            let mut hits: Vec<PxSweepHit> = Vec::with_capacity(Self::MAX_HITS);
+            let mut blocking_hit = false;
+            let count = shape.with_geometry(|geometry| {
+                PxSceneQueryExt_sweepMultiple(
+                    physx_lock.scene.get_raw(),
+                    geometry,
+                    &isometry_to_px(pose),
+                    &to_px_vec3(&direction),
+                    max_distance,
+                    PxHitFlags { mBits: PxHitFlag::eDEFAULT as u16 },
+                    hits.as_mut_ptr(),
+                    Self::MAX_HITS as u32,
+                    &mut blocking_hit,
+                    &filter.to_px_touching(),
+                    null_mut(),
+                    null(),
+                    0.0,
+                )
+            });
+            hits.set_len(if count < 0 { Self::MAX_HITS } else { count as usize });
+            hits
+        };
+
+        self.closest(hits.iter().map(|hit| (hit.actor as usize, hit.position, hit.normal, hit.distance)))
+    }
+
+    /// Closest of the hits whose actor belongs to an entity.
+    fn closest(&self, hits: impl Iterator<Item = (usize, PxVec3, PxVec3, f32)>) -> Option<QueryHit> {
+        hits.filter_map(|(actor, position, normal, distance)| {
+            Some(QueryHit {
+                entity: self.bodies.entity_by_ptr(actor)?,
+                position: Point3::new(position.x, position.y, position.z),
+                normal: Vector3::new(normal.x, normal.y, normal.z),
+                distance,
+            })
+        })
+        .min_by(|a, b| a.distance.partial_cmp(&b.distance).unwrap_or(std::cmp::Ordering::Equal))
+    }
+
+    pub fn sweep_sphere(&self, radius: f32, pose: &Isometry3<f32>, direction: Unit<Vector3<f32>>, max_distance: f32, filter: QueryFilter) -> Option<QueryHit> {
+        self.sweep(QueryShape::Sphere { radius }, pose, direction, max_distance, filter)
+    }
+
+    pub fn sweep_box(&self, half_extents: [f32; 3], pose: &Isometry3<f32>, direction: Unit<Vector3<f32>>, max_distance: f32, filter: QueryFilter) -> Option<QueryHit> {
+        self.sweep(QueryShape::Box { half_extents }, pose, direction, max_distance, filter)
+    }
+
+    pub fn sweep_capsule(&self, radius: f32, half_height: f32, pose: &Isometry3<f32>, direction: Unit<Vector3<f32>>, max_distance: f32, filter: QueryFilter) -> Option<QueryHit> {
+        self.sweep(QueryShape::Capsule { radius, half_height }, pose, direction, max_distance, filter)
+    }
+
+    /// Returns every entity with a shape overlapping `shape` at `pose`, at most `MAX_OVERLAPS`.
+    pub fn overlap(&self, shape: QueryShape, pose: &Isometry3<f32>, filter: QueryFilter) -> Vec<Entity> {
+        let physx_lock = self.physx.0.as_ref().unwrap().lock().unwrap();
+
+        let hits = unsafe {
+            let mut hits: Vec<PxOverlapHit> = Vec::with_capacity(Self::MAX_OVERLAPS);
+            let count = shape.with_geometry(|geometry| {
+                PxSceneQueryExt_overlapMultiple(
+                    physx_lock.scene.get_raw(),
+                    geometry,
+                    &isometry_to_px(pose),
+                    hits.as_mut_ptr(),
+                    Self::MAX_OVERLAPS as u32,
+                    &filter.to_px(),
+                    null_mut(),
+                )
+            });
+            // A negative count means the buffer overflowed and is completely filled
+            hits.set_len(if count < 0 { Self::MAX_OVERLAPS } else { count as usize });
+            hits
+        };
+
+        let mut entities: Vec<Entity> = hits
+            .iter()
+            .filter_map(|hit| self.bodies.entity_by_ptr(hit.actor as usize))
+            .collect();
+        // Compound actors report one hit per shape
+        entities.sort();
+        entities.dedup();
+        entities
+    }
+
+    pub fn overlap_sphere(&self, radius: f32, pose: &Isometry3<f32>, filter: QueryFilter) -> Vec<Entity> {
+        self.overlap(QueryShape::Sphere { radius }, pose, filter)
+    }
+
+    pub fn overlap_box(&self, half_extents: [f32; 3], pose: &Isometry3<f32>, filter: QueryFilter) -> Vec<Entity> {
+        self.overlap(QueryShape::Box { half_extents }, pose, filter)
+    }
+
+    pub fn overlap_capsule(&self, radius: f32, half_height: f32, pose: &Isometry3<f32>, filter: QueryFilter) -> Vec<Entity> {
+        self.overlap(QueryShape::Capsule { radius, half_height }, pose, filter)
+    }
+
+    /// World space bounding box of the entity's actor, as `(min, max)`.
+    pub fn world_bounds(&self, entity: Entity) -> Option<(Point3<f32>, Point3<f32>)> {
+        let handle = self.bodies.actor(entity)?;
+        let _physx_lock = self.physx.0.as_ref().unwrap().lock().unwrap();
+
+        let bounds = unsafe { PxActor_getWorldBounds(handle.0 as *const PxActor, 1.0) };
+        Some((
+            Point3::new(bounds.minimum.x, bounds.minimum.y, bounds.minimum.z),
+            Point3::new(bounds.maximum.x, bounds.maximum.y, bounds.maximum.z),
+        ))
+    }
+}
+
+fn to_px_vec3(vector: &Vector3<f32>) -> PxVec3 {
+    PxVec3 { x: vector.x, y: vector.y, z: vector.z }
+}
@@ -0,0 +1,346 @@
+use amethyst::{
+    animation::Skin,
+    core::{
+        math::{Isometry3, Translation3, UnitQuaternion, Vector3},
+        transform::Transform,
+        ParentHierarchy,
+    },
+    derive::SystemDesc,
+    ecs::{
+        Component, DenseVecStorage, Entity, Join, ReadStorage, System, SystemData, WriteStorage,
+    },
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+use crate::articulation::{
+    Articulation, ArticulationAxis, ArticulationDrive, ArticulationJoint, ArticulationJointKind,
+    ArticulationLink,
+};
+use crate::collider::{Collider, ColliderShape};
+
+/// Settings for generating a `Ragdoll` from a skin.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RagdollConfig {
+    /// Capsule radius relative to the length of its bone.
+    pub radius_ratio: f32,
+    pub min_radius: f32,
+    pub max_radius: f32,
+    /// Radius of the spheres on bones without child bones, like fingertips and the head.
+    pub leaf_radius: f32,
+    pub density: f32,
+    /// Twist limit around the bone in radians, in both directions from the bind pose.
+    pub twist_limit: f32,
+    /// Swing limit away from the bone in radians, in both directions from the bind pose.
+    pub swing_limit: f32,
+    /// Damping of every joint, keeps the limbs from flailing around.
+    pub joint_damping: f32,
+}
+
+impl Default for RagdollConfig {
+    fn default() -> Self {
+        RagdollConfig {
+            radius_ratio: 0.2,
+            min_radius: 0.02,
+            max_radius: 0.15,
+            leaf_radius: 0.05,
+            density: 1000.0,
+            twist_limit: std::f32::consts::FRAC_PI_6,
+            swing_limit: std::f32::consts::FRAC_PI_4,
+            joint_damping: 5.0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RagdollMode {
+    /// The skeleton is posed by its animations and has no physics components.
+    Animated,
+    /// The skeleton is simulated as articulations, starting from the current pose.
+    Simulated,
+}
+
+/// Generated body of a single bone.
+#[derive(Clone, Debug)]
+pub struct RagdollBone {
+    pub entity: Entity,
+    pub collider: Collider,
+    /// Joint to the parent bone, `None` for root bones which become `Articulation` roots.
+    pub link: Option<ArticulationLink>,
+}
+
+/// Physics representation of a skinned skeleton, switched between animation and simulation
+/// through `mode`.
+///
+/// In `RagdollMode::Simulated` every bone gets a capsule `Collider` and the skeleton becomes an
+/// articulation per root bone, which `ArticulationSystem` writes back into the bone `Transform`s.
+/// Pause the animations driving the skeleton while it is simulated, otherwise they keep
+/// overwriting the bones.
+#[derive(Clone, Debug)]
+pub struct Ragdoll {
+    pub mode: RagdollMode,
+    pub density: f32,
+    pub bones: Vec<RagdollBone>,
+    applied: Option<RagdollMode>,
+    /// Colliders the bones had before simulating, restored when they are animated again.
+    replaced: Vec<(Entity, Collider)>,
+}
+
+impl Ragdoll {
+    /// Generates a capsule per bone of `skin` and a limited spherical joint between every bone and
+    /// its parent bone, starting in `RagdollMode::Animated`.
+    pub fn from_skin(
+        skin: &Skin,
+        transforms: &ReadStorage<'_, Transform>,
+        hierarchy: &ParentHierarchy,
+        config: &RagdollConfig,
+    ) -> Self {
+        Ragdoll::from_joints(&skin.joints, transforms, hierarchy, config)
+    }
+
+    /// Same as `from_skin`, for a skeleton given by its bone entities.
+    pub fn from_joints(
+        bones: &[Entity],
+        transforms: &ReadStorage<'_, Transform>,
+        hierarchy: &ParentHierarchy,
+        config: &RagdollConfig,
+    ) -> Self {
+        let joints: HashSet<Entity> = bones.iter().copied().collect();
+
+        let bones = bones
+            .iter()
+            .filter(|bone| transforms.contains(**bone))
+            .map(|&bone| {
+                let child_bones: Vec<Entity> = hierarchy
+                    .children(bone)
+                    .iter()
+                    .filter(|child| joints.contains(child) && transforms.contains(**child))
+                    .copied()
+                    .collect();
+                let (collider, bone_rotation) = bone_collider(&child_bones, transforms, config);
+
+                let is_root = hierarchy.parent(bone).map_or(true, |parent| !joints.contains(&parent));
+                let link = if is_root {
+                    None
+                } else {
+                    Some(bone_link(bone_rotation, config))
+                };
+
+                RagdollBone { entity: bone, collider, link }
+            })
+            .collect();
+
+        Ragdoll {
+            mode: RagdollMode::Animated,
+            density: config.density,
+            bones,
+            applied: None,
+            replaced: Vec::new(),
+        }
+    }
+
+    pub fn body_count(&self) -> usize {
+        self.bones.len()
+    }
+
+    pub fn joint_count(&self) -> usize {
+        self.bones.iter().filter(|bone| bone.link.is_some()).count()
+    }
+}
+
+impl Component for Ragdoll {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Capsule from the bone's origin to its child bone, and the rotation turning the X axis along the
+/// bone. Bones branching into several children, like the hips into the spine and legs, get a box
+/// around the start of their children instead.
+fn bone_collider(
+    child_bones: &[Entity],
+    transforms: &ReadStorage<'_, Transform>,
+    config: &RagdollConfig,
+) -> (Collider, UnitQuaternion<f32>) {
+    let children: Vec<Vector3<f32>> = child_bones
+        .iter()
+        .map(|child| *transforms.get(*child).unwrap().translation())
+        .collect();
+
+    if children.len() > 1 {
+        let (min, max) = children
+            .iter()
+            .fold((Vector3::zeros(), Vector3::zeros()), |(min, max), child| (min.inf(child), max.sup(child)));
+        let half_extents = ((max - min) / 2.0).map(|extent| extent.max(config.min_radius).min(config.max_radius));
+        let shape = ColliderShape::Box { half_extents: [half_extents.x, half_extents.y, half_extents.z] };
+        let collider = Collider::new(shape).with_offset((min + max) / 2.0, UnitQuaternion::identity());
+        return (collider, UnitQuaternion::identity());
+    }
+
+    let tip = children.first().copied().unwrap_or_else(Vector3::zeros);
+    let length = tip.norm();
+
+    if length < 1.0e-4 {
+        let shape = ColliderShape::Sphere { radius: config.leaf_radius };
+        return (Collider::new(shape), UnitQuaternion::identity());
+    }
+
+    // Capsules extend along their X axis
+    let rotation = UnitQuaternion::rotation_between(&Vector3::x(), &tip)
+        .unwrap_or_else(|| UnitQuaternion::from_axis_angle(&Vector3::y_axis(), std::f32::consts::PI));
+    let radius = (length * config.radius_ratio).max(config.min_radius).min(config.max_radius);
+    let half_height = length / 2.0 - radius;
+    let shape = if half_height > 0.0 {
+        ColliderShape::Capsule { radius, half_height }
+    } else {
+        ColliderShape::Sphere { radius: length / 2.0 }
+    };
+
+    (Collider::new(shape).with_offset(tip / 2.0, rotation), rotation)
+}
+
+fn bone_link(bone_rotation: UnitQuaternion<f32>, config: &RagdollConfig) -> ArticulationLink {
+    let damping = ArticulationDrive {
+        stiffness: 0.0,
+        damping: config.joint_damping,
+        max_force: std::f32::MAX,
+        target: 0.0,
+        target_velocity: 0.0,
+    };
+
+    // Twist around the bone, the joint sits at the bone's origin
+    let mut joint = ArticulationJoint::new(ArticulationJointKind::Spherical)
+        .with_limit(ArticulationAxis::Twist, -config.twist_limit, config.twist_limit)
+        .with_limit(ArticulationAxis::Swing1, -config.swing_limit, config.swing_limit)
+        .with_limit(ArticulationAxis::Swing2, -config.swing_limit, config.swing_limit)
+        .with_drive(ArticulationAxis::Twist, damping)
+        .with_drive(ArticulationAxis::Swing1, damping)
+        .with_drive(ArticulationAxis::Swing2, damping);
+    joint.child_frame = Isometry3::from_parts(Translation3::identity(), bone_rotation);
+
+    ArticulationLink {
+        joint,
+        density: config.density,
+    }
+}
+
+/// Adds or removes the physics components of the bones when a `Ragdoll` changes its mode.
+///
+/// Switching back to `RagdollMode::Animated` only removes the components added for the simulation,
+/// a ragdoll that starts out animated leaves the bones untouched. Colliders the bones already had
+/// are replaced while simulating and restored afterwards.
+#[derive(SystemDesc)]
+pub struct RagdollSystem;
+impl<'a> System<'a> for RagdollSystem {
+    type SystemData = (
+        WriteStorage<'a, Ragdoll>,
+        WriteStorage<'a, Articulation>,
+        WriteStorage<'a, ArticulationLink>,
+        WriteStorage<'a, Collider>,
+    );
+
+    fn run(&mut self, (mut ragdolls, mut articulations, mut links, mut colliders): Self::SystemData) {
+        for ragdoll in (&mut ragdolls).join() {
+            if ragdoll.applied == Some(ragdoll.mode) {
+                continue;
+            }
+
+            let simulated = ragdoll.applied == Some(RagdollMode::Simulated);
+            match ragdoll.mode {
+                RagdollMode::Simulated => {
+                    for bone in &ragdoll.bones {
+                        if let Some(collider) = colliders.get(bone.entity) {
+                            ragdoll.replaced.push((bone.entity, collider.clone()));
+                        }
+                        // Bones of deleted skeletons fail to insert, which is fine
+                        colliders.insert(bone.entity, bone.collider.clone()).ok();
+                        match &bone.link {
+                            Some(link) => {
+                                links.insert(bone.entity, link.clone()).ok();
+                            }
+                            None => {
+                                let root = Articulation { fix_base: false, density: ragdoll.density };
+                                articulations.insert(bone.entity, root).ok();
+                            }
+                        }
+                    }
+                }
+                RagdollMode::Animated if simulated => {
+                    for bone in &ragdoll.bones {
+                        colliders.remove(bone.entity);
+                        links.remove(bone.entity);
+                        articulations.remove(bone.entity);
+                    }
+                    for (entity, collider) in ragdoll.replaced.drain(..) {
+                        colliders.insert(entity, collider).ok();
+                    }
+                }
+                RagdollMode::Animated => {}
+            }
+            ragdoll.applied = Some(ragdoll.mode);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use amethyst::{
+        core::{
+            transform::{Parent, TransformBundle},
+            SystemBundle,
+        },
+        ecs::{Builder, DispatcherBuilder, World, WorldExt},
+    };
+
+    fn bone(world: &mut World, parent: Option<Entity>, translation: Vector3<f32>) -> Entity {
+        let mut transform = Transform::default();
+        transform.set_translation(translation);
+        let builder = world.create_entity().with(transform);
+        match parent {
+            Some(parent) => builder.with(Parent::new(parent)).build(),
+            None => builder.build(),
+        }
+    }
+
+    #[test]
+    fn generates_body_per_bone_and_joint_per_child() {
+        let mut world = World::new();
+        let mut builder = DispatcherBuilder::new();
+        TransformBundle::new().build(&mut world, &mut builder).unwrap();
+        let mut dispatcher = builder.build();
+        dispatcher.setup(&mut world);
+
+        // Hips with a spine and head above them and a leg below, like the sample skeleton
+        let hips = bone(&mut world, None, Vector3::new(0.0, 1.0, 0.0));
+        let spine = bone(&mut world, Some(hips), Vector3::new(0.0, 0.3, 0.0));
+        let head = bone(&mut world, Some(spine), Vector3::new(0.0, 0.3, 0.0));
+        let leg = bone(&mut world, Some(hips), Vector3::new(0.0, -0.5, 0.0));
+        dispatcher.dispatch(&world);
+        world.maintain();
+
+        let ragdoll = Ragdoll::from_joints(
+            &[hips, spine, head, leg],
+            &world.read_storage::<Transform>(),
+            &world.read_resource::<ParentHierarchy>(),
+            &RagdollConfig::default(),
+        );
+
+        assert_eq!(ragdoll.body_count(), 4);
+        assert_eq!(ragdoll.joint_count(), 3);
+        let hips = ragdoll.bones.iter().find(|bone| bone.entity == hips).unwrap();
+        assert!(hips.link.is_none());
+        // The spine goes up and the leg down, so the hips get a box spanning both
+        match hips.collider.shape {
+            ColliderShape::Box { half_extents: [_, y, _] } => assert_eq!(y, RagdollConfig::default().max_radius),
+            ref shape => panic!("Expected a box on the hips, got {:?}", shape),
+        }
+        match ragdoll.bones.iter().find(|bone| bone.entity == spine).unwrap().collider.shape {
+            ColliderShape::Capsule { .. } => {}
+            ref shape => panic!("Expected a capsule on the spine, got {:?}", shape),
+        }
+        match ragdoll.bones.iter().find(|bone| bone.entity == head).unwrap().collider.shape {
+            ColliderShape::Sphere { radius } => assert_eq!(radius, RagdollConfig::default().leaf_radius),
+            ref shape => panic!("Expected a sphere on the head, got {:?}", shape),
+        }
+    }
+}
@@ -0,0 +1,132 @@
+use amethyst::utils::application_root_dir;
+use glam::Vec3;
+use physx::prelude::*;
+use physx::scene::VisualizationParameter;
+use physx::visual_debugger::PvdSceneClient;
+use physx_sys::{
+    destroy_simulation_event_callbacks, destroy_user_controller_hit_report,
+    phys_PxCreateControllerManager, PxControllerManager, PxControllerManager_release_mut, PxMaterial_release_mut,
+    PxSceneFlag, PxScene_setBounceThresholdVelocity_mut, PxScene_setFlag_mut,
+    PxSimulationEventCallback, PxUserControllerHitReport,
+};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use crate::config::PhysicsConfig;
+use crate::cooking::MeshCooker;
+use crate::events::{simulation_filter_shader, SimulationEvents};
+use crate::material::PhysicsMaterials;
+
+pub const PX_PHYSICS_VERSION: u32 = physx::version(4, 1, 1);
+
+pub struct PhysxResources {
+    pub foundation: Foundation,
+    pub physics: Option<Physics>,
+    pub scene: Box<Scene>,
+    pub pvd_scene_client: Option<Box<PvdSceneClient>>,
+    pub cooker: Option<MeshCooker>,
+    pub material: *mut physx_sys::PxMaterial,
+    pub(crate) materials: PhysicsMaterials,
+    pub controller_manager: *mut PxControllerManager,
+    pub(crate) events: Box<SimulationEvents>,
+    event_callback: *mut PxSimulationEventCallback,
+    pub(crate) controller_hit_report: *mut PxUserControllerHitReport,
+}
+
+impl PhysxResources {
+    pub fn new(config: &PhysicsConfig) -> PhysxResources {
+        let mut foundation = Foundation::new(PX_PHYSICS_VERSION);
+        let mut physics = PhysicsBuilder::default()
+            .load_extensions(config.load_extensions)
+            .build(&mut foundation);
+        let [x, y, z] = config.gravity;
+        let mut scene = physics.create_scene(
+            SceneBuilder::default()
+                .set_gravity(Vec3::new(x, y, z))
+                .set_simulation_threading(SimulationThreadType::Dedicated(config.dispatcher_threads))
+                .set_solver_type(config.solver.to_px())
+                .set_broad_phase_type(config.broadphase.to_px())
+                .set_simulation_filter_shader(simulation_filter_shader),
+        );
+        unsafe {
+            let px_scene = scene.get_raw_mut();
+            PxScene_setBounceThresholdVelocity_mut(px_scene, config.bounce_threshold);
+            let flags = &config.scene_flags;
+            PxScene_setFlag_mut(px_scene, PxSceneFlag::eENABLE_ACTIVE_ACTORS, flags.active_actors);
+            PxScene_setFlag_mut(
+                px_scene,
+                PxSceneFlag::eEXCLUDE_KINEMATICS_FROM_ACTIVE_ACTORS,
+                flags.exclude_kinematics_from_active_actors,
+            );
+        }
+
+        let mut events = Box::new(SimulationEvents::default());
+        let event_callback = unsafe { SimulationEvents::register(&mut scene, &mut events) };
+        let controller_hit_report = unsafe { SimulationEvents::register_controller_hits(&mut events) };
+
+        if config.debug_visualization {
+            scene.set_visualization_parameter(VisualizationParameter::Scale, 1.0);
+            scene.set_visualization_parameter(VisualizationParameter::ContactPoint, 1.0);
+            scene.set_visualization_parameter(VisualizationParameter::ContactForce, 1.0);
+            scene.set_visualization_parameter(VisualizationParameter::ContactNormal, 1.0);
+            scene.set_visualization_parameter(VisualizationParameter::CollisionShapes, 1.0);
+            scene.set_visualization_parameter(VisualizationParameter::WorldAxes, 1.0);
+        }
+
+        let controller_manager = unsafe { phys_PxCreateControllerManager(scene.get_raw_mut(), false) };
+
+        let pvd_scene_client = Some(Box::new(scene.get_pvd_client()));
+
+        let mut cooker = MeshCooker::new(PX_PHYSICS_VERSION, &mut foundation, &physics)
+            .expect("Failed to create the PhysX cooking library");
+        if let Some(mesh_cache) = &config.mesh_cache {
+            let cache_dir = application_root_dir().map_or_else(|_| mesh_cache.clone(), |root| root.join(mesh_cache));
+            cooker = cooker.with_cache(cache_dir);
+        }
+        let cooker = Some(cooker);
+        let material_config = &config.default_material;
+        let material = physics.create_material(
+            material_config.static_friction,
+            material_config.dynamic_friction,
+            material_config.restitution,
+        );
+
+        PhysxResources{foundation, physics: Some(physics), scene, pvd_scene_client, cooker, material, materials: PhysicsMaterials::default(), controller_manager, events, event_callback, controller_hit_report}
+    }
+}
+
+impl Drop for PhysxResources {
+    fn drop(&mut self) {
+        self.pvd_scene_client = None;
+        self.cooker = None;
+        unsafe{
+            // Releases the controllers and their actors as well
+            PxControllerManager_release_mut(self.controller_manager);
+            self.scene.release();
+            destroy_simulation_event_callbacks(self.event_callback);
+            destroy_user_controller_hit_report(self.controller_hit_report);
+            self.materials.release_all();
+            PxMaterial_release_mut(self.material);
+            //This calls drop implicitly
+            self.physics = None;
+            self.foundation.release();
+        }
+    }
+}
+
+#[derive(Default, Clone)]
+pub struct PhysXRef(pub(crate) Option<Arc<Mutex<PhysxResources>>>);
+unsafe impl Send for PhysXRef {}
+unsafe impl Sync for PhysXRef {}
+
+impl PhysXRef {
+    pub fn new(resources: PhysxResources) -> Self {
+        PhysXRef(Some(Arc::new(Mutex::new(resources))))
+    }
+
+    /// Locks the PhysX resources for raw PhysX calls from game systems. Returns `None` before
+    /// `PhysicsBundle` created them, after they were released or when another thread panicked
+    /// while holding the lock.
+    pub fn lock(&self) -> Option<MutexGuard<'_, PhysxResources>> {
+        self.0.as_ref()?.lock().ok()
+    }
+}
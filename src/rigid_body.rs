@@ -0,0 +1,232 @@
+use amethyst::{
+    assets::AssetStorage,
+    core::transform::Transform,
+    derive::SystemDesc,
+    ecs::{
+        Component, DenseVecStorage, Entities, FlaggedStorage, Join, Read, ReadStorage, System,
+        SystemData, Write, WriteStorage,
+    },
+};
+use physx::prelude::*;
+use physx_sys::{
+    PxActor, PxActor_release_mut, PxMaterial, PxPhysics_createRigidDynamic_mut,
+    PxPhysics_createRigidStatic_mut, PxRigidActor, PxRigidActor_getGlobalPose, PxRigidBody,
+    PxRigidBodyExt_updateMassAndInertia_mut_1, PxRigidBodyFlag, PxRigidBody_setAngularDamping_mut,
+    PxRigidBody_setLinearDamping_mut, PxRigidBody_setRigidBodyFlag_mut, PxScene_addActor_mut,
+    PxTransform,
+};
+use serde::{Deserialize, Serialize};
+use std::ops::DerefMut;
+use std::ptr::null;
+
+use crate::articulation::{Articulation, ArticulationLink};
+use crate::bodies::PhysicsBodies;
+use crate::collider::{supported_collider, Collider};
+use crate::cooking::MeshCooker;
+use crate::interpolation::PhysicsPose;
+use crate::layers::CollisionLayers;
+use crate::material::PhysicsMaterial;
+use crate::transform_conv::{apply_px_transform, px_to_isometry, to_px_transform};
+use crate::resources::{PhysXRef, PhysxResources};
+
+/// How the actor of a `RigidBody` is simulated.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BodyType {
+    /// Never moves, created with `createRigidStatic`.
+    Static,
+    /// Simulated by PhysX, the resulting pose is written back to `Transform`.
+    Dynamic,
+    /// Follows the entity's `Transform`, which is set as the kinematic target every step.
+    Kinematic,
+}
+
+impl Default for BodyType {
+    fn default() -> Self {
+        BodyType::Dynamic
+    }
+}
+
+/// Connects an entity's `Transform` to a PhysX actor.
+///
+/// The actor is created from the `Transform` the first time `RigidBodySyncSystem` sees the
+/// entity, so bodies are expected to live on entities without a `Parent`. Changing `body_type`
+/// rebuilds the actor at the current `Transform`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RigidBody {
+    pub body_type: BodyType,
+    /// Used to compute mass and inertia from the attached `Collider`.
+    pub density: f32,
+    pub linear_damping: f32,
+    pub angular_damping: f32,
+}
+
+impl Default for RigidBody {
+    fn default() -> Self {
+        RigidBody {
+            body_type: BodyType::Dynamic,
+            density: 1.0,
+            linear_damping: 0.0,
+            angular_damping: 0.05,
+        }
+    }
+}
+
+impl RigidBody {
+    pub fn new(body_type: BodyType) -> Self {
+        RigidBody {
+            body_type,
+            ..Default::default()
+        }
+    }
+
+    unsafe fn create_actor(
+        &self,
+        pose: &PxTransform,
+        collider: Option<&Collider>,
+        physics: &mut Physics,
+        cooker: &MeshCooker,
+        material: *mut PxMaterial,
+        layers: &CollisionLayers,
+    ) -> *mut PxRigidActor {
+        if self.body_type == BodyType::Static {
+            return create_static_actor(pose, collider, physics, cooker, material, layers);
+        }
+
+        let actor = PxPhysics_createRigidDynamic_mut(physics.get_raw_mut(), pose) as *mut PxRigidBody;
+        if self.body_type == BodyType::Kinematic {
+            PxRigidBody_setRigidBodyFlag_mut(actor, PxRigidBodyFlag::eKINEMATIC, true);
+        }
+        PxRigidBody_setLinearDamping_mut(actor, self.linear_damping);
+        PxRigidBody_setAngularDamping_mut(actor, self.angular_damping);
+        if let Some(collider) = supported_collider(collider, self.body_type) {
+            collider.attach_to(actor as *mut PxRigidActor, physics, cooker, material, layers);
+        }
+        PxRigidBodyExt_updateMassAndInertia_mut_1(actor, self.density, null(), false);
+
+        actor as *mut PxRigidActor
+    }
+}
+
+impl Component for RigidBody {
+    type Storage = FlaggedStorage<Self, DenseVecStorage<Self>>;
+}
+
+unsafe fn create_static_actor(
+    pose: &PxTransform,
+    collider: Option<&Collider>,
+    physics: &mut Physics,
+    cooker: &MeshCooker,
+    material: *mut PxMaterial,
+    layers: &CollisionLayers,
+) -> *mut PxRigidActor {
+    let actor = PxPhysics_createRigidStatic_mut(physics.get_raw_mut(), pose) as *mut PxRigidActor;
+    if let Some(collider) = collider {
+        collider.attach_to(actor, physics, cooker, material, layers);
+    }
+    actor
+}
+
+/// Creates actors for new `RigidBody` and `Collider` components and writes the simulated poses of
+/// dynamic bodies back to `Transform`. Has to run after the PhysX step has fetched its results.
+/// Creating an actor waits until the `PhysicsMaterial` of its collider has loaded. Entities that
+/// are part of an `Articulation` are left to `ArticulationSystem`.
+#[derive(SystemDesc)]
+pub struct RigidBodySyncSystem;
+impl<'a> System<'a> for RigidBodySyncSystem {
+    type SystemData = (
+        Entities<'a>,
+        Write<'a, PhysXRef>,
+        Write<'a, PhysicsBodies>,
+        Read<'a, AssetStorage<PhysicsMaterial>>,
+        Read<'a, CollisionLayers>,
+        ReadStorage<'a, RigidBody>,
+        ReadStorage<'a, Collider>,
+        ReadStorage<'a, Articulation>,
+        ReadStorage<'a, ArticulationLink>,
+        WriteStorage<'a, PhysicsPose>,
+        WriteStorage<'a, Transform>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            physx,
+            mut bodies,
+            material_storage,
+            layers,
+            rigid_bodies,
+            colliders,
+            articulation_roots,
+            articulation_links,
+            mut poses,
+            mut transforms,
+        ): Self::SystemData,
+    ) {
+        let mut physx_lock = physx.0.as_ref().unwrap().lock().unwrap();
+        let PhysxResources { physics, scene, cooker, material: default_material, materials, .. } = physx_lock.deref_mut();
+        let physics = physics.as_mut().unwrap();
+        let cooker = cooker.as_ref().unwrap();
+
+        let mut articulated = articulation_roots.mask().clone();
+        articulated |= articulation_links.mask();
+
+        for (entity, rigid_body, transform, ()) in (&entities, &rigid_bodies, &mut transforms, !&articulated).join() {
+            let existing = bodies.get(entity).map(|(handle, body_type)| (handle.0, body_type));
+            match existing {
+                Some((actor, body_type)) if body_type == rigid_body.body_type => {
+                    if body_type == BodyType::Dynamic {
+                        let pose = unsafe { PxRigidActor_getGlobalPose(actor as *const PxRigidActor) };
+                        if let Some(physics_pose) = poses.get_mut(entity) {
+                            physics_pose.current = px_to_isometry(&pose);
+                        }
+                        apply_px_transform(&pose, transform);
+                    }
+                }
+                _ => unsafe {
+                    let material = match materials.resolve(colliders.get(entity), &material_storage, physics, *default_material) {
+                        Some(material) => material,
+                        None => continue,
+                    };
+
+                    // Either a new body or its type changed, which needs a different kind of actor
+                    if let Some(old_handle) = bodies.remove(entity) {
+                        PxActor_release_mut(old_handle.0 as *mut PxActor);
+                        poses.remove(entity);
+                    }
+
+                    let pose = to_px_transform(transform);
+                    let actor = rigid_body.create_actor(&pose, colliders.get(entity), physics, cooker, material, &layers);
+                    PxScene_addActor_mut(scene.get_raw_mut(), actor as *mut PxActor, null());
+
+                    bodies.insert(entity, BodyHandle(actor as usize), rigid_body.body_type);
+                    if rigid_body.body_type == BodyType::Dynamic {
+                        poses
+                            .insert(entity, PhysicsPose::new(px_to_isometry(&pose)))
+                            .expect("Failed to insert PhysicsPose for a live entity");
+                    }
+                },
+            }
+        }
+
+        for (entity, collider, transform, (), ()) in
+            (&entities, &colliders, &transforms, !&rigid_bodies, !&articulated).join()
+        {
+            if bodies.contains(entity) {
+                continue;
+            }
+            let material = match materials.resolve(Some(collider), &material_storage, physics, *default_material) {
+                Some(material) => material,
+                None => continue,
+            };
+
+            unsafe {
+                let actor = create_static_actor(&to_px_transform(transform), Some(collider), physics, cooker, material, &layers);
+                PxScene_addActor_mut(scene.get_raw_mut(), actor as *mut PxActor, null());
+
+                bodies.insert(entity, BodyHandle(actor as usize), BodyType::Static);
+            }
+        }
+    }
+}
@@ -0,0 +1,64 @@
+use amethyst::core::{
+    math::{Isometry3, Matrix3, Matrix4, Quaternion, Rotation3, Translation3, UnitQuaternion, Vector3},
+    transform::Transform,
+};
+use physx_sys::{PxQuat, PxTransform, PxVec3};
+
+pub fn to_px_transform(transform: &Transform) -> PxTransform {
+    let p = transform.translation();
+    let q = transform.rotation();
+
+    PxTransform {
+        q: PxQuat { x: q.i, y: q.j, z: q.k, w: q.w },
+        p: PxVec3 { x: p.x, y: p.y, z: p.z },
+    }
+}
+
+pub fn apply_px_transform(pose: &PxTransform, transform: &mut Transform) {
+    transform.set_translation(Vector3::new(pose.p.x, pose.p.y, pose.p.z));
+    transform.set_rotation(UnitQuaternion::from_quaternion(Quaternion::new(
+        pose.q.w, pose.q.x, pose.q.y, pose.q.z,
+    )));
+}
+
+pub fn px_to_isometry(pose: &PxTransform) -> Isometry3<f32> {
+    Isometry3::from_parts(
+        Translation3::new(pose.p.x, pose.p.y, pose.p.z),
+        UnitQuaternion::from_quaternion(Quaternion::new(pose.q.w, pose.q.x, pose.q.y, pose.q.z)),
+    )
+}
+
+pub fn isometry_to_px(isometry: &Isometry3<f32>) -> PxTransform {
+    let p = &isometry.translation.vector;
+    let q = &isometry.rotation;
+
+    PxTransform {
+        q: PxQuat { x: q.i, y: q.j, z: q.k, w: q.w },
+        p: PxVec3 { x: p.x, y: p.y, z: p.z },
+    }
+}
+
+/// Rotation and translation of a transformation matrix, dropping any scale.
+pub fn matrix_to_isometry(matrix: &Matrix4<f32>) -> Isometry3<f32> {
+    let column = |i| Vector3::new(matrix[(0, i)], matrix[(1, i)], matrix[(2, i)]).normalize();
+    let rotation = Rotation3::from_matrix_unchecked(Matrix3::from_columns(&[column(0), column(1), column(2)]));
+
+    Isometry3::from_parts(
+        Translation3::new(matrix[(0, 3)], matrix[(1, 3)], matrix[(2, 3)]),
+        UnitQuaternion::from_rotation_matrix(&rotation),
+    )
+}
+
+/// World space pose of a `Transform` as of the last transform system run, without scale.
+pub fn global_isometry(transform: &Transform) -> Isometry3<f32> {
+    matrix_to_isometry(transform.global_matrix())
+}
+
+/// Sets the translation and rotation of a `Transform` with a `Parent`, so that it ends up at the
+/// world space `pose`. The scale of the transform is kept.
+pub fn apply_world_pose(pose: &Isometry3<f32>, parent_global: &Matrix4<f32>, transform: &mut Transform) {
+    let parent_inverse = parent_global.try_inverse().unwrap_or_else(Matrix4::identity);
+    let local = matrix_to_isometry(&(parent_inverse * pose.to_homogeneous()));
+    transform.set_translation(local.translation.vector);
+    transform.set_rotation(local.rotation);
+}